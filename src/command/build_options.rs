@@ -0,0 +1,249 @@
+use crate::{
+    absolute_path::AbsolutePath,
+    external_tool::{diagnostic::MessageFormat, job_pool::JobPool},
+    manifest::{features::FeatureRequest, sanitizer::Sanitizer},
+    workspace::Workspace,
+};
+use std::path::PathBuf;
+
+/// Options shared by every command that builds a package (`build`, `run`).
+#[derive(Clone, Default)]
+pub struct BuildOptions {
+    pub path: AbsolutePath,
+    pub profile: String,
+    pub target: Option<String>,
+    pub jobs: usize,
+    pub message_format: MessageFormat,
+    pub verbose: bool,
+    pub sanitize: Vec<Sanitizer>,
+    pub features: FeatureRequest,
+    pub bin: Option<String>,
+    pub example: Option<String>,
+    pub packages: Vec<String>,
+    /// Directory holding `build/`: the package's, or its workspace's.
+    pub build_root: Option<AbsolutePath>,
+    /// Set by `test`: build the tests whose name contains one of these
+    /// filters (every test when empty) instead of the binaries.
+    pub tests: Option<Vec<String>>,
+    /// Set by `test` when its report goes to stdout: status lines go to
+    /// stderr, without a progress bar.
+    pub stdout_reserved: bool,
+    /// Set by `test --coverage`: instrument the build, in its own directory.
+    pub coverage: bool,
+    /// An argument that is well-formed but invalid, such as an unknown
+    /// sanitizer: reported as an error when the command runs.
+    pub invalid: Option<String>,
+}
+
+impl BuildOptions {
+    /// Parses `[--debug|--release|--profile NAME] [--target NAME] [-j|--jobs N]
+    /// [--message-format human|json] [-v|--verbose] [--sanitize LIST] [--features LIST]
+    /// [--no-default-features] [--all-features] [--bin NAME|--example NAME] [-p|--package NAME]
+    /// [path]` in any order.
+    ///
+    /// `--jobs=N`, `-jN`, `--message-format=FORMAT`, `--sanitize=LIST`,
+    /// `--features=LIST` and `--package=NAME` are accepted as well; `--features`
+    /// and `--package` may be repeated.
+    pub fn parse(args: &[String]) -> Option<Self> {
+        let mut profile = "debug".to_string();
+        let mut target = None;
+        let mut jobs = JobPool::default_workers();
+        let mut message_format = MessageFormat::default();
+        let mut verbose = false;
+        let mut sanitize = vec![];
+        let mut invalid = None;
+        let mut features = FeatureRequest::default();
+        let mut bin = None;
+        let mut example = None;
+        let mut packages = vec![];
+        let mut path = None;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if let Some(name) = parse_profile_flag(arg, &mut args)? {
+                profile = name;
+                continue;
+            }
+
+            if let Some(name) = parse_target_flag(arg, &mut args)? {
+                target = Some(name);
+                continue;
+            }
+
+            match arg.as_str() {
+                "-j" | "--jobs" => jobs = Self::parse_jobs(args.next()?)?,
+                "-v" | "--verbose" => verbose = true,
+                "--sanitize" => match Self::parse_sanitizers(args.next()?) {
+                    Ok(requested) => sanitize = requested,
+                    Err(e) => invalid = Some(e),
+                },
+                "--features" => features.features.extend(Self::parse_features(args.next()?)),
+                "--no-default-features" => features.default_features = false,
+                "--all-features" => features.all_features = true,
+                "--bin" => bin = Some(args.next()?.clone()),
+                "--example" => example = Some(args.next()?.clone()),
+                "-p" | "--package" => packages.push(args.next()?.clone()),
+                "--message-format" => {
+                    message_format = MessageFormat::try_from(args.next()?.as_str()).ok()?
+                }
+                _ => {
+                    if let Some(value) = arg.strip_prefix("--sanitize=") {
+                        match Self::parse_sanitizers(value) {
+                            Ok(requested) => sanitize = requested,
+                            Err(e) => invalid = Some(e),
+                        }
+                    } else if let Some(value) = arg.strip_prefix("--features=") {
+                        features.features.extend(Self::parse_features(value));
+                    } else if let Some(value) = arg.strip_prefix("--package=") {
+                        packages.push(value.to_string());
+                    } else if let Some(value) = arg.strip_prefix("--message-format=") {
+                        message_format = MessageFormat::try_from(value).ok()?;
+                    } else if let Some(value) = arg
+                        .strip_prefix("--jobs=")
+                        .or_else(|| arg.strip_prefix("-j"))
+                    {
+                        jobs = Self::parse_jobs(value)?;
+                    } else if arg.starts_with('-') || path.is_some() {
+                        return None;
+                    } else {
+                        path = Some(PathBuf::from(arg));
+                    }
+                }
+            }
+        }
+
+        if bin.is_some() && example.is_some() {
+            return None;
+        }
+
+        let path = match path {
+            Some(path) => path.try_into().ok()?,
+            None => std::env::current_dir().ok()?.try_into().ok()?,
+        };
+
+        Some(Self {
+            path,
+            profile,
+            target,
+            jobs,
+            message_format,
+            verbose,
+            sanitize,
+            features,
+            bin,
+            example,
+            packages,
+            build_root: None,
+            tests: None,
+            stdout_reserved: false,
+            coverage: false,
+            invalid,
+        })
+    }
+
+    /// Packages to work on: the members of the workspace `path` is part of,
+    /// selected with `-p` (see [`Workspace::select`]), or the package at `path`.
+    ///
+    /// Builds of a workspace share the `build/` directory of its root.
+    ///
+    /// As every building command starts here, an invalid argument is reported here too.
+    pub fn resolve_workspace(&mut self) -> Result<(Option<Workspace>, Vec<AbsolutePath>), String> {
+        if let Some(invalid) = &self.invalid {
+            return Err(invalid.clone());
+        }
+        let Some(workspace) = Workspace::find(&self.path)? else {
+            if !self.packages.is_empty() {
+                return Err(format!(
+                    "`-p` selects members of a workspace, but {} is not part of one",
+                    self.path.inner().display()
+                ));
+            }
+            return Ok((None, vec![self.path.clone()]));
+        };
+
+        let members = workspace.select(&self.path, &self.packages)?;
+        self.build_root = Some(workspace.root().clone());
+        Ok((Some(workspace), members))
+    }
+
+    /// `build/[<target>/]<profile>` inside the package directory, or inside the
+    /// workspace root for members of a workspace.
+    ///
+    /// Sanitized builds get their own directory, e.g. `debug+address+undefined`,
+    /// so their objects never mix with regular ones.
+    pub fn output_dir(&self) -> PathBuf {
+        let mut path = self
+            .build_root
+            .as_ref()
+            .unwrap_or(&self.path)
+            .inner()
+            .join("build");
+        if let Some(target) = &self.target {
+            path = path.join(target);
+        }
+        let mut name = self.profile.clone();
+        for sanitizer in &self.sanitize {
+            name.push_str(&format!("+{}", sanitizer));
+        }
+        if self.coverage {
+            name.push_str("+coverage");
+        }
+        path.join(name)
+    }
+
+    /// Parses a comma-separated sanitizer list, deduplicated and in a canonical
+    /// order so that equivalent lists share an output directory.
+    fn parse_sanitizers(value: &str) -> Result<Vec<Sanitizer>, String> {
+        let requested = value
+            .split(',')
+            .map(|name| Sanitizer::try_from(name.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Sanitizer::ALL
+            .into_iter()
+            .filter(|sanitizer| requested.contains(sanitizer))
+            .collect())
+    }
+
+    /// Parses a comma- or space-separated feature list, e.g. `"zlib,fast"`.
+    fn parse_features(value: &str) -> Vec<String> {
+        value
+            .split([',', ' '])
+            .filter(|feature| !feature.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    fn parse_jobs(value: &str) -> Option<usize> {
+        value.parse::<usize>().ok().filter(|jobs| *jobs > 0)
+    }
+}
+
+/// Parses a profile selection flag, consuming the profile name from `rest` for `--profile`.
+///
+/// Returns `Some(None)` when `arg` is not a profile flag and `None` when the flag is malformed.
+pub fn parse_profile_flag<'a, I>(arg: &str, rest: &mut I) -> Option<Option<String>>
+where
+    I: Iterator<Item = &'a String>,
+{
+    match arg {
+        "--debug" => Some(Some("debug".to_string())),
+        "--release" => Some(Some("release".to_string())),
+        "--profile" => rest.next().map(|name| Some(name.clone())),
+        _ => match arg.strip_prefix("--profile=") {
+            Some(name) => Some(Some(name.to_string())),
+            None => Some(None),
+        },
+    }
+}
+
+/// Parses `--target NAME`/`--target=NAME`, with the same conventions as [`parse_profile_flag`].
+pub fn parse_target_flag<'a, I>(arg: &str, rest: &mut I) -> Option<Option<String>>
+where
+    I: Iterator<Item = &'a String>,
+{
+    match arg {
+        "--target" => rest.next().map(|name| Some(name.clone())),
+        _ => Some(arg.strip_prefix("--target=").map(String::from)),
+    }
+}
@@ -1,21 +1,76 @@
 use crate::{
-    absolute_path::AbsolutePath,
-    command::Command,
-    external_tool::{compiler::Compiler, registry::Registry},
+    command::{Command, build_options::BuildOptions},
+    external_tool::{
+        compiler::{BuiltObjects, Compiler},
+        diagnostic::MessageFormat,
+        registry::Registry,
+    },
     fmt::success,
-    manifest::Manifest,
-    mode::Mode,
+    lockfile::Lockfile,
+    manifest::{
+        Manifest,
+        package_type::PackageType,
+        profile::Profile,
+        target::{Target, TargetKind},
+    },
     package::Package,
+    toolchain::Toolchain,
 };
-use std::{path::PathBuf, time::Instant};
+use std::time::Instant;
 
 #[derive(Default)]
 pub struct BuildPkg {
-    path: AbsolutePath,
-    mode: Mode,
+    options: BuildOptions,
     registry: Registry,
 }
 
+impl BuildPkg {
+    pub fn new(options: BuildOptions) -> Self {
+        Self {
+            options,
+            registry: Registry::default(),
+        }
+    }
+}
+
+/// Binaries and examples to build besides the package itself: every binary,
+/// or only the one selected with `--bin`, and the example selected with `--example`.
+///
+/// When testing, the tests matching the filters instead.
+pub fn selected_targets(
+    options: &BuildOptions,
+    manifest: &Manifest,
+) -> Result<Vec<Target>, String> {
+    if let Some(filters) = &options.tests {
+        return Ok(manifest
+            .tests()
+            .iter()
+            .filter(|test| {
+                filters.is_empty() || filters.iter().any(|filter| test.name().contains(filter))
+            })
+            .cloned()
+            .collect());
+    }
+
+    let mut targets = match &options.bin {
+        // `--bin <package>` names the default binary of a bin package.
+        Some(name)
+            if name == manifest.name()
+                && manifest.pkg_type() == PackageType::Binary
+                && manifest.target(TargetKind::Bin, name).is_err() =>
+        {
+            vec![]
+        }
+        Some(name) => vec![manifest.target(TargetKind::Bin, name)?.clone()],
+        None => manifest.bins().to_vec(),
+    };
+    if let Some(name) = &options.example {
+        targets.push(manifest.target(TargetKind::Example, name)?.clone());
+    }
+
+    Ok(targets)
+}
+
 impl Command for BuildPkg {
     fn parse_args(&mut self, args: &[String]) -> Option<()>
     where
@@ -25,76 +80,134 @@ impl Command for BuildPkg {
             return None;
         }
 
-        match args.len() {
-            1 => {
-                self.mode = Mode::Debug;
-                self.path = std::env::current_dir().ok()?.try_into().ok()?;
+        self.options = BuildOptions::parse(&args[1..])?;
 
-                Some(())
-            }
-            2 => {
-                match args[1].as_str().try_into() {
-                    Ok(mode) => {
-                        self.mode = mode;
-                        self.path = std::env::current_dir().ok()?.try_into().ok()?;
-                    }
-                    Err(_) => {
-                        self.mode = Mode::Debug;
-                        self.path = PathBuf::from(&args[1]).try_into().ok()?;
-                    }
-                }
-
-                Some(())
-            }
-            3 => {
-                let mode = match args[1].as_str().try_into() {
-                    Ok(mode) => mode,
-                    Err(_) => return None,
-                };
+        Some(())
+    }
 
-                self.mode = mode;
-                self.path = PathBuf::from(&args[2]).try_into().ok()?;
+    fn execute(&self) -> Result<(), String> {
+        self.build().map(|_| ())
+    }
+}
 
-                Some(())
+impl BuildPkg {
+    /// Builds the selected packages, returning the objects of each.
+    pub fn build(&self) -> Result<Vec<BuiltObjects>, String> {
+        let start = Instant::now();
+        let mut options = self.options.clone();
+        let (workspace, selected) = options.resolve_workspace()?;
+        let mut lockfile = match &workspace {
+            Some(workspace) => Lockfile::load(workspace.root(), workspace.members())?,
+            None => Lockfile::load(&options.path, &selected)?,
+        };
+
+        let mut built = Ok(None);
+        let mut objects = vec![];
+        for member in selected {
+            options.path = member;
+            built = self
+                .build_package(&options, &mut lockfile)
+                .map(|(profile, built)| {
+                    objects.push(built);
+                    Some(profile)
+                });
+            if built.is_err() {
+                break;
             }
-            _ => None,
         }
+        let built = built.and_then(|profile| lockfile.write().map(|_| profile));
+
+        if let MessageFormat::Json = self.options.message_format {
+            println!(
+                "{}",
+                serde_json::json!({ "reason": "build-finished", "success": built.is_ok() })
+            );
+        }
+        let Some(profile) = built? else {
+            return Ok(objects);
+        };
+        let finished = format!(
+            "{} `{}` profile target{}{} in {:.2}s",
+            success("Finished"),
+            profile.name(),
+            match &self.options.target {
+                Some(target) => format!(" for `{}`", target),
+                None => "".to_string(),
+            },
+            match profile.sanitizers() {
+                [] => "".to_string(),
+                sanitizers => format!(
+                    " with {} sanitizer{}",
+                    sanitizers
+                        .iter()
+                        .map(|sanitizer| sanitizer.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    if sanitizers.len() > 1 { "s" } else { "" }
+                ),
+            },
+            start.elapsed().as_secs_f32()
+        );
+        match (self.options.message_format, self.options.stdout_reserved) {
+            (MessageFormat::Human, false) => println!("{}", finished),
+            _ => eprintln!("{}", finished),
+        }
+
+        Ok(objects)
     }
 
-    fn execute(&self) -> Result<(), String> {
-        let start = Instant::now();
-        let manifest_content = std::fs::read_to_string(self.path.inner().join("Tailor.toml"))
+    /// Builds the package at `options.path`, returning the profile it was
+    /// built with and its objects.
+    ///
+    /// The resolved packages are added to `lockfile`.
+    fn build_package(
+        &self,
+        options: &BuildOptions,
+        lockfile: &mut Lockfile,
+    ) -> Result<(Profile, BuiltObjects), String> {
+        let manifest_content = std::fs::read_to_string(options.path.inner().join("Tailor.toml"))
             .map_err(|_| "fail to read Tailor.toml".to_string())?;
-        let manifest = Manifest::from_file(&manifest_content, &self.path)?;
-        let pkg = Package::load_from_manifest(manifest, &self.registry)?;
+        let manifest = Manifest::from_file(&manifest_content, &options.path)?;
+        let targets = selected_targets(options, &manifest)?;
+        let pkg = match targets.iter().any(|target| target.is_dev()) {
+            true => Package::load_with_dev_dependencies(
+                manifest,
+                &options.features,
+                &self.registry,
+                lockfile,
+            )?,
+            false => {
+                Package::load_from_manifest(manifest, &options.features, &self.registry, lockfile)?
+            }
+        };
+        lockfile.add(&pkg);
 
         let manifest = pkg.manifest();
         let pkg_type = manifest.pkg_type();
-        let base_path = self.path.inner().join("build");
-        let defines = pkg
-            .options()
-            .into_iter()
-            .map(|def| def.to_define())
-            .collect();
-
-        let path = match self.mode {
-            Mode::Debug => base_path.join("debug"),
-            Mode::Release => base_path.join("release"),
+
+        let mut profile = manifest.profile(&options.profile)?.clone();
+        profile.add_sanitizers(&options.sanitize);
+        profile.set_coverage(options.coverage);
+        let toolchain = match &options.target {
+            Some(target) => Some(Toolchain::load(target, &options.path)?),
+            None => None,
         };
+        let path = options.output_dir();
         std::fs::create_dir_all(&path)
             .map_err(|e| format!("fail to create build directory: {}", e))?;
 
-        let compiler = Compiler::new(manifest.compiler(), manifest.full_name());
+        let compiler = Compiler::new(
+            manifest.compiler(),
+            manifest.full_name(),
+            options.jobs,
+            toolchain,
+            options.message_format,
+            options.stdout_reserved,
+            options.verbose,
+        )?;
 
-        compiler.build(self.mode, &path, pkg, pkg_type, defines)?;
-
-        println!(
-            "{} `{}` profile target in {:.2}s",
-            success("Finished"),
-            self.mode,
-            start.elapsed().as_secs_f32()
-        );
+        let objects = compiler.build(&profile, &path, pkg, pkg_type, &targets)?;
 
-        Ok(())
+        Ok((profile, objects))
     }
 }
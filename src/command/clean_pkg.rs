@@ -1,9 +1,22 @@
-use crate::{absolute_path::AbsolutePath, command::Command, fmt::success, manifest::Manifest};
+use crate::{
+    absolute_path::AbsolutePath,
+    command::{
+        Command,
+        build_options::{parse_profile_flag, parse_target_flag},
+    },
+    fmt::success,
+    manifest::Manifest,
+    toolchain::Toolchain,
+    workspace::Workspace,
+};
 use std::path::{Path, PathBuf};
 
 #[derive(Default)]
 pub struct CleanPkg {
     path: AbsolutePath,
+    profile: Option<String>,
+    target: Option<String>,
+    packages: Vec<String>,
 }
 
 impl CleanPkg {
@@ -72,43 +85,147 @@ impl Command for CleanPkg {
             return None;
         }
 
-        match args.len() {
-            1 => {
-                self.path = std::env::current_dir().ok()?.try_into().ok()?;
+        let mut path = None;
+        let mut rest = args[1..].iter();
+        while let Some(arg) = rest.next() {
+            if let Some(name) = parse_profile_flag(arg, &mut rest)? {
+                self.profile = Some(name);
+            } else if let Some(name) = parse_target_flag(arg, &mut rest)? {
+                self.target = Some(name);
+            } else if arg == "-p" || arg == "--package" {
+                self.packages.push(rest.next()?.clone());
+            } else if let Some(name) = arg.strip_prefix("--package=") {
+                self.packages.push(name.to_string());
+            } else if arg.starts_with('-') || path.is_some() {
+                return None;
+            } else {
+                path = Some(PathBuf::from(arg));
+            }
+        }
+
+        self.path = match path {
+            Some(path) => path.try_into().ok()?,
+            None => std::env::current_dir().ok()?.try_into().ok()?,
+        };
+
+        Some(())
+    }
 
-                Some(())
+    fn execute(&self) -> Result<(), String> {
+        let workspace = Workspace::find(&self.path)?;
+        let build_root = match &workspace {
+            Some(workspace) => workspace.root(),
+            None if !self.packages.is_empty() => {
+                return Err(format!(
+                    "`-p` selects members of a workspace, but {} is not part of one",
+                    self.path.inner().display()
+                ));
             }
-            2 => {
-                self.path = PathBuf::from(&args[1]).try_into().ok()?;
+            None => &self.path,
+        };
+        // A workspace root without a package has no profiles to look names up in.
+        let manifest = match &workspace {
+            Some(workspace) if !workspace.is_member(self.path.inner()) => None,
+            _ => {
+                let manifest_content =
+                    std::fs::read_to_string(self.path.inner().join("Tailor.toml"))
+                        .map_err(|_| "fail to read Tailor.toml".to_string())?;
+                Some(Manifest::from_file(&manifest_content, &self.path)?)
+            }
+        };
+
+        // The toolchain file is not needed: it may be gone along with the target.
+        let mut build_path = build_root.join("build").inner().to_owned();
+        if let Some(target) = &self.target {
+            Toolchain::check_name(target)?;
+            build_path = build_path.join(target);
+        }
+        let build_paths = match &self.profile {
+            Some(profile) => Self::profile_dirs(
+                &build_path,
+                match &manifest {
+                    Some(manifest) => manifest.profile(profile)?.name(),
+                    None => profile,
+                },
+            ),
+            None => vec![build_path],
+        };
 
-                Some(())
+        if let Some(workspace) = &workspace
+            && !self.packages.is_empty()
+        {
+            let mut artifacts = vec![];
+            for member in workspace.select(&self.path, &self.packages)? {
+                let manifest = Workspace::load(&member)?;
+                for build_path in &build_paths {
+                    Self::package_artifacts(build_path, &manifest, &mut artifacts);
+                }
             }
-            _ => None,
+            return Self::remove(&artifacts);
         }
-    }
 
-    fn execute(&self) -> Result<(), String> {
-        let manifest_content = std::fs::read_to_string(self.path.inner().join("Tailor.toml"))
-            .map_err(|_| "fail to read Tailor.toml".to_string())?;
-        let _manifest = Manifest::from_file(&manifest_content, &self.path)?;
+        Self::remove(&build_paths)
+    }
+}
 
-        let build_path = self.path.join("build").inner().to_owned();
+impl CleanPkg {
+    /// The directories of `profile` in `directory`: its own, and those of its
+    /// sanitized and coverage builds, e.g. `debug+address` or `debug+coverage`.
+    fn profile_dirs(directory: &Path, profile: &str) -> Vec<PathBuf> {
+        let variants = format!("{}+", profile);
+        let mut paths = vec![directory.join(profile)];
+        if let Ok(entries) = std::fs::read_dir(directory) {
+            let mut found = entries
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with(&variants))
+                .map(|entry| entry.path())
+                .collect::<Vec<_>>();
+            found.sort();
+            paths.extend(found);
+        }
+        paths
+    }
 
-        let total_files = if build_path.exists() {
-            Self::count_dir_recursively(&build_path)
-        } else {
-            0
+    /// Artifacts of `manifest` in a shared build directory: its objects,
+    /// archives, executables, build script outputs and generated headers.
+    fn package_artifacts(directory: &Path, manifest: &Manifest, artifacts: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(directory) else {
+            return;
         };
 
-        let total_size = if build_path.exists() {
-            Self::dir_size(&build_path)
-        } else {
-            0
-        };
+        let full_name = manifest.full_name();
+        let library = format!("lib{}.", manifest.name());
+        let in_gen = directory.file_name().is_some_and(|name| name == "gen");
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == full_name
+                || name.starts_with(&format!("{}.", full_name))
+                || (name.starts_with(&library) && path.is_file())
+                || (in_gen && name == manifest.name())
+            {
+                artifacts.push(path);
+            } else if path.is_dir() {
+                Self::package_artifacts(&path, manifest, artifacts);
+            }
+        }
+    }
 
-        if build_path.exists() {
-            std::fs::remove_dir_all(&build_path)
-                .map_err(|e| format!("Failed to clean build directory: {}", e))?;
+    fn remove(paths: &[PathBuf]) -> Result<(), String> {
+        let mut total_files = 0;
+        let mut total_size = 0;
+        for path in paths {
+            if path.is_dir() {
+                total_files += Self::count_dir_recursively(path);
+                total_size += Self::dir_size(path);
+                std::fs::remove_dir_all(path)
+                    .map_err(|e| format!("Failed to clean build directory: {}", e))?;
+            } else if let Ok(metadata) = std::fs::metadata(path) {
+                total_files += 1;
+                total_size += metadata.len();
+                std::fs::remove_file(path)
+                    .map_err(|e| format!("Failed to clean build directory: {}", e))?;
+            }
         }
 
         println!(
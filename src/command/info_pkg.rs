@@ -0,0 +1,150 @@
+use crate::{
+    absolute_path::AbsolutePath,
+    command::Command,
+    external_tool::registry::Registry,
+    lockfile::Lockfile,
+    manifest::{
+        Manifest, features::FeatureRequest, options_schema::OptionSchema, package_type::PackageType,
+    },
+    package::Package,
+    workspace::Workspace,
+};
+use std::path::PathBuf;
+
+/// `tailor info [path]`: describes a package and its dependencies, with the
+/// features they enable by default, the options each of them accepts and the
+/// values they resolve to in this build.
+#[derive(Default)]
+pub struct InfoPkg {
+    path: AbsolutePath,
+    registry: Registry,
+}
+
+impl InfoPkg {
+    fn describe(manifest: &Manifest) {
+        println!(
+            "{} v{} ({})",
+            manifest.name(),
+            manifest.version(),
+            match manifest.pkg_type() {
+                PackageType::Binary => "bin",
+                PackageType::Library => "lib",
+                PackageType::HeaderOnly => "header-only",
+            }
+        );
+
+        let features = manifest.features().names();
+        if !features.is_empty() {
+            println!(
+                "  features: {}",
+                features
+                    .iter()
+                    .map(
+                        |feature| match manifest.enabled_features().iter().any(|f| f == feature) {
+                            true => format!("+{}", feature),
+                            false => format!("-{}", feature),
+                        }
+                    )
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+
+        let value_of = |name: &str| {
+            manifest
+                .options()
+                .iter()
+                .find(|option| option.key == name)
+                .map(|option| option.value.to_string())
+                .unwrap_or("unset".to_string())
+        };
+
+        match manifest.options_schema() {
+            Some(schema) if !schema.options().is_empty() => {
+                println!("  options:");
+                for option in schema.options() {
+                    println!(
+                        "    {} ({}) = {}",
+                        option.name,
+                        Self::constraints(option),
+                        value_of(&option.name)
+                    );
+                    if let Some(description) = &option.description {
+                        println!("        {}", description);
+                    }
+                }
+            }
+            Some(_) => println!("  options: none"),
+            None if manifest.options().is_empty() => println!("  options: none declared"),
+            None => {
+                println!("  options (no schema):");
+                for option in manifest.options() {
+                    println!("    {} = {}", option.key, option.value);
+                }
+            }
+        }
+    }
+
+    /// e.g. `integer, default 6, range 0 to 9`.
+    fn constraints(option: &OptionSchema) -> String {
+        let mut constraints = vec![option.type_.to_string()];
+        if let Some(default) = &option.default {
+            constraints.push(format!("default {}", default));
+        }
+        if !option.values.is_empty() {
+            constraints.push(format!(
+                "one of {}",
+                option
+                    .values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if let Some((min, max)) = option.range {
+            constraints.push(format!("range {} to {}", min, max));
+        }
+        constraints.join(", ")
+    }
+}
+
+impl Command for InfoPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "info" {
+            return None;
+        }
+
+        let path = match &args[1..] {
+            [] => std::env::current_dir().ok()?,
+            [path] if !path.starts_with('-') => PathBuf::from(path),
+            _ => return None,
+        };
+        self.path = path.try_into().ok()?;
+
+        Some(())
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let manifest_content = std::fs::read_to_string(self.path.inner().join("Tailor.toml"))
+            .map_err(|_| "fail to read Tailor.toml".to_string())?;
+        let manifest = Manifest::from_file(&manifest_content, &self.path)?;
+        let lockfile = match Workspace::find(&self.path)? {
+            Some(workspace) => Lockfile::load(workspace.root(), workspace.members())?,
+            None => Lockfile::load(&self.path, std::slice::from_ref(&self.path))?,
+        };
+        let pkg = Package::load_from_manifest(
+            manifest,
+            &FeatureRequest::default(),
+            &self.registry,
+            &lockfile,
+        )?;
+
+        Self::describe(pkg.manifest());
+        for dependency in pkg.dependencies() {
+            Self::describe(dependency);
+        }
+
+        Ok(())
+    }
+}
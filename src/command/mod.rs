@@ -1,7 +1,10 @@
+pub mod build_options;
 pub mod build_pkg;
 pub mod clean_pkg;
+pub mod info_pkg;
 pub mod new_pkg;
 pub mod run_pkg;
+pub mod test_pkg;
 
 pub trait Command {
     fn parse_args(&mut self, args: &[String]) -> Option<()>;
@@ -37,6 +37,7 @@ impl Command for NewPkg {
                 match args[1].as_str() {
                     "--bin" => self.pkg_type = PackageType::Binary,
                     "--lib" => self.pkg_type = PackageType::Library,
+                    "--header-only" => self.pkg_type = PackageType::HeaderOnly,
                     _ => return None,
                 }
 
@@ -58,6 +59,7 @@ impl Command for NewPkg {
         match self.pkg_type {
             PackageType::Binary => bin::new_pkg(&self.path, &self.name),
             PackageType::Library => lib::new_pkg(&self.path, &self.name),
+            PackageType::HeaderOnly => header_only::new_pkg(&self.path, &self.name),
         }
     }
 }
@@ -142,3 +144,42 @@ mod lib {
         Ok(())
     }
 }
+
+mod header_only {
+    use std::path::Path;
+
+    use crate::fmt::success;
+
+    const HEADER_ONLY_H: &str = include_str!("../../template/header_only.h");
+    const TAILOR_MANIFEST: &str = include_str!("../../template/header-only/Tailor.toml");
+
+    pub fn new_pkg(path: &Path, name: &str) -> Result<(), String> {
+        if path.exists() {
+            return Err(format!("destination `{}` already exists.", path.display()));
+        }
+
+        std::fs::create_dir_all(path.join(format!("include/{name}/")))
+            .map_err(|_| "fail to create include".to_string())?;
+
+        std::fs::write(
+            path.join(format!("include/{name}/{name}.h")),
+            HEADER_ONLY_H
+                .replace("$pkg_name_guard", &format!("{}_H", name.to_uppercase()))
+                .replace("$pkg_name", name),
+        )
+        .map_err(|_| format!("fail to write include/{name}/{name}.h"))?;
+
+        std::fs::write(
+            path.join("Tailor.toml"),
+            TAILOR_MANIFEST.replace("$pkg_name", name),
+        )
+        .map_err(|_| "fail to write Tailor.toml".to_string())?;
+
+        println!(
+            "{} header-only library package `{name}`",
+            success("Creating")
+        );
+
+        Ok(())
+    }
+}
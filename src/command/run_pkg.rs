@@ -1,19 +1,14 @@
 use crate::{
-    absolute_path::AbsolutePath,
-    command::{Command, build_pkg::BuildPkg},
-    external_tool::registry::Registry,
+    command::{Command, build_options::BuildOptions, build_pkg::BuildPkg},
+    external_tool::diagnostic::MessageFormat,
     fmt::success,
-    manifest::{Manifest, package_type::PackageType},
-    mode::Mode,
-    package::Package,
+    manifest::{Manifest, package_type::PackageType, target::TargetKind},
+    toolchain::Toolchain,
 };
-use std::path::PathBuf;
 
 #[derive(Default)]
 pub struct RunPkg {
-    mode: Mode,
-    path: AbsolutePath,
-    registry: Registry,
+    options: BuildOptions,
 }
 
 impl Command for RunPkg {
@@ -22,86 +17,96 @@ impl Command for RunPkg {
             return None;
         }
 
-        match args.len() {
-            1 => {
-                self.mode = Mode::Debug;
-                self.path = std::env::current_dir().ok()?.try_into().ok()?;
+        self.options = BuildOptions::parse(&args[1..])?;
 
-                Some(())
-            }
-            2 => {
-                match args[1].as_str().try_into() {
-                    Ok(mode) => {
-                        self.mode = mode;
-                        self.path = std::env::current_dir().ok()?.try_into().ok()?;
-                    }
-                    Err(_) => {
-                        self.mode = Mode::Debug;
-                        self.path = PathBuf::from(&args[1]).try_into().ok()?;
-                    }
-                }
-
-                Some(())
-            }
-            3 => {
-                let mode = match args[1].as_str().try_into() {
-                    Ok(mode) => mode,
-                    Err(_) => return None,
-                };
-
-                self.mode = mode;
-                self.path = PathBuf::from(&args[2]).try_into().ok()?;
-
-                Some(())
-            }
-            _ => None,
-        }
+        Some(())
     }
 
     fn execute(&self) -> Result<(), String> {
-        let mode_name = self.mode.to_string();
+        let mut options = self.options.clone();
+        let (_, selected) = options.resolve_workspace()?;
+        let [member] = &selected[..] else {
+            return Err(
+                "`run` needs a single package; select a member of the workspace with -p NAME"
+                    .to_string(),
+            );
+        };
+        options.path = member.clone();
+        options.packages = vec![];
 
-        let manifest_content = std::fs::read_to_string(self.path.inner().join("Tailor.toml"))
+        let manifest_content = std::fs::read_to_string(options.path.inner().join("Tailor.toml"))
             .map_err(|_| "fail to read Tailor.toml")?;
-        let manifest = Manifest::from_file(&manifest_content, &self.path)?;
-        let pkg = Package::load_from_manifest(manifest, &self.registry)?;
+        let manifest = &Manifest::from_file(&manifest_content, &options.path)?;
+        let executable_path = match (&options.bin, &options.example) {
+            (_, Some(name)) => manifest
+                .target(TargetKind::Example, name)?
+                .output_path(&options.output_dir(), manifest),
+            (Some(name), None) => match manifest.target(TargetKind::Bin, name) {
+                Ok(target) => target.output_path(&options.output_dir(), manifest),
+                Err(_) if name == manifest.name() && manifest.pkg_type() == PackageType::Binary => {
+                    options.output_dir().join(manifest.full_name())
+                }
+                Err(e) => return Err(e),
+            },
+            (None, None) => match manifest.pkg_type() {
+                PackageType::Binary => options.output_dir().join(manifest.full_name()),
+                PackageType::Library | PackageType::HeaderOnly => {
+                    let names = manifest
+                        .bins()
+                        .iter()
+                        .map(|bin| format!("--bin {}", bin.name()))
+                        .chain(
+                            manifest
+                                .examples()
+                                .iter()
+                                .map(|example| format!("--example {}", example.name())),
+                        )
+                        .collect::<Vec<_>>();
+                    return Err(match names.is_empty() {
+                        true => "It's not possible run a library package".to_string(),
+                        false => format!(
+                            "a library package has no default binary; run one of its targets with {}",
+                            names.join(", ")
+                        ),
+                    });
+                }
+            },
+        };
 
-        let pkg_type = pkg.manifest().pkg_type();
-        let pkg_name = pkg.manifest().full_name();
+        BuildPkg::new(options.clone()).execute()?;
 
-        match pkg_type {
-            PackageType::Library => Err("It's not possible run a library package".to_string()),
-            PackageType::Binary => {
-                let mut build = BuildPkg::default();
-                build
-                    .parse_args(&[
-                        "build".to_string(),
-                        format!("--{}", mode_name),
-                        self.path.inner().to_string_lossy().to_string(),
-                    ])
-                    .ok_or("Failed to parse build arguments".to_string())?;
-                build.execute()?;
+        let runner = match &options.target {
+            Some(target) => Toolchain::load(target, &options.path)?.runner().to_vec(),
+            None => vec![],
+        };
 
-                let executable_path = self
-                    .path
-                    .inner()
-                    .join("build")
-                    .join(mode_name)
-                    .join(&pkg_name);
-                println!(
-                    "{} `{}`",
-                    success("Running"),
-                    executable_path.to_string_lossy()
-                );
-                let status = std::process::Command::new(executable_path)
-                    .status()
-                    .map_err(|e| format!("failed to execute the package: {}", e))?;
-                if !status.success() {
-                    return Err("execution failed".to_string());
-                }
+        let mut command_line = runner.clone();
+        command_line.push(executable_path.to_string_lossy().to_string());
+        let running = format!("{} `{}`", success("Running"), command_line.join(" "));
+        match options.message_format {
+            MessageFormat::Human => println!("{}", running),
+            MessageFormat::Json => eprintln!("{}", running),
+        }
 
-                Ok(())
-            }
+        // Sanitizer defaults, unless the user configured the sanitizer already
+        let mut profile = manifest.profile(&options.profile)?.clone();
+        profile.add_sanitizers(&options.sanitize);
+        let sanitizer_env = profile
+            .sanitizers()
+            .iter()
+            .filter_map(|sanitizer| sanitizer.runtime_options())
+            .filter(|(variable, _)| std::env::var_os(variable).is_none())
+            .collect::<Vec<_>>();
+
+        let status = std::process::Command::new(&command_line[0])
+            .args(&command_line[1..])
+            .envs(sanitizer_env)
+            .status()
+            .map_err(|e| format!("failed to execute the package: {}", e))?;
+        if !status.success() {
+            return Err("execution failed".to_string());
         }
+
+        Ok(())
     }
 }
@@ -0,0 +1,351 @@
+use crate::{
+    command::{
+        Command,
+        build_options::BuildOptions,
+        build_pkg::{BuildPkg, selected_targets},
+    },
+    external_tool::{
+        compiler_family::CompilerFamily,
+        coverage::Coverage,
+        diagnostic::MessageFormat,
+        test_report::{ReportFormat, TestReport},
+        test_runner::{Outcome, TestResult, TestRunner},
+    },
+    fmt::success,
+    storage::Storage,
+    toolchain::Toolchain,
+    workspace::Workspace,
+};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Seconds a test may run before it is killed, unless `--timeout` says otherwise.
+const DEFAULT_TIMEOUT: u64 = 60;
+
+#[derive(Default)]
+pub struct TestPkg {
+    options: BuildOptions,
+    timeout: Option<u64>,
+    format: ReportFormat,
+    output: Option<String>,
+    /// Also measure the dependencies downloaded to `~/.tailor/packages`.
+    coverage_deps: bool,
+}
+
+impl TestPkg {
+    fn parse_timeout(value: &str) -> Option<u64> {
+        value.parse::<u64>().ok().filter(|timeout| *timeout > 0)
+    }
+
+    /// Prints a line of the report: on stdout, unless stdout is reserved for
+    /// JSON messages or a JUnit or TAP report.
+    fn report(&self, line: &str) {
+        match (self.options.message_format, self.options.stdout_reserved) {
+            (MessageFormat::Human, false) => println!("{}", line),
+            _ => eprintln!("{}", line),
+        }
+    }
+
+    /// Reads the coverage counts of the tests that ran, prints a summary and
+    /// writes `lcov.info` and `cobertura.xml` to `<build_path>/coverage`.
+    ///
+    /// Only the tested packages' `objects` are measured: not the tests, nor
+    /// what the build generated, nor (unless asked for) dependencies.
+    fn report_coverage(
+        &self,
+        gcov: &[String],
+        build_path: &Path,
+        root: &Path,
+        name: &str,
+        objects: &[PathBuf],
+        test_mains: &[PathBuf],
+    ) -> Result<(), String> {
+        let storage = Storage::storage_dir()?;
+        let storage = storage
+            .inner()
+            .canonicalize()
+            .unwrap_or(storage.inner().to_path_buf());
+        let generated = root.join("build");
+
+        let coverage = Coverage::collect(gcov, build_path, objects, |path| {
+            let path = path.canonicalize().unwrap_or(path.to_path_buf());
+            !test_mains.contains(&path)
+                && !path.starts_with(&generated)
+                && (self.coverage_deps || !path.starts_with(&storage))
+        })?;
+
+        let directory = build_path.join("coverage");
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| format!("fail to create {}: {}", directory.display(), e))?;
+        let lcov = directory.join("lcov.info");
+        std::fs::write(&lcov, coverage.lcov())
+            .map_err(|e| format!("failed to write {}: {}", lcov.display(), e))?;
+        let cobertura = directory.join("cobertura.xml");
+        std::fs::write(&cobertura, coverage.cobertura(root, name))
+            .map_err(|e| format!("failed to write {}: {}", cobertura.display(), e))?;
+
+        self.report("");
+        for line in coverage.summary(root) {
+            self.report(&line);
+        }
+        self.report(&format!(
+            "\n{} written to {} and {}",
+            success("Coverage"),
+            lcov.display(),
+            cobertura.display()
+        ));
+        Ok(())
+    }
+}
+
+impl Command for TestPkg {
+    /// Parses `test [build options] [--timeout SECS] [--format FORMAT]
+    /// [--output FILE] [--coverage [--coverage-deps]] [path] [-- FILTER...]`.
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "test" {
+            return None;
+        }
+
+        let (args, filters) = match args[1..].iter().position(|arg| arg == "--") {
+            Some(index) => (&args[1..index + 1], args[index + 2..].to_vec()),
+            None => (&args[1..], vec![]),
+        };
+
+        let mut build_args = vec![];
+        let mut coverage = false;
+        let mut rest = args.iter();
+        while let Some(arg) = rest.next() {
+            if arg == "--timeout" {
+                self.timeout = Some(Self::parse_timeout(rest.next()?)?);
+            } else if let Some(value) = arg.strip_prefix("--timeout=") {
+                self.timeout = Some(Self::parse_timeout(value)?);
+            } else if arg == "--format" {
+                self.format = ReportFormat::try_from(rest.next()?.as_str()).ok()?;
+            } else if let Some(value) = arg.strip_prefix("--format=") {
+                self.format = ReportFormat::try_from(value).ok()?;
+            } else if arg == "--output" {
+                self.output = Some(rest.next()?.clone());
+            } else if let Some(value) = arg.strip_prefix("--output=") {
+                self.output = Some(value.to_string());
+            } else if arg == "--coverage" {
+                coverage = true;
+            } else if arg == "--coverage-deps" {
+                self.coverage_deps = true;
+            } else {
+                build_args.push(arg.clone());
+            }
+        }
+
+        self.options = BuildOptions::parse(&build_args)?;
+        if self.options.bin.is_some() || self.options.example.is_some() {
+            return None;
+        }
+        if self.coverage_deps && !coverage {
+            return None;
+        }
+        self.options.coverage = coverage;
+        // A report goes to stdout only when the JSON messages do not.
+        if self.format != ReportFormat::Human
+            && self.output.is_none()
+            && self.options.message_format == MessageFormat::Json
+        {
+            return None;
+        }
+        self.options.tests = Some(filters);
+        self.options.stdout_reserved = self.format != ReportFormat::Human && self.output.is_none();
+
+        Some(())
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let built = BuildPkg::new(self.options.clone()).build()?;
+
+        let start = Instant::now();
+        let mut options = self.options.clone();
+        let (workspace, members) = options.resolve_workspace()?;
+        let qualified = members.len() > 1;
+        // Reports are named after the package, or the workspace's directory.
+        let mut suite_name = workspace
+            .as_ref()
+            .filter(|_| qualified)
+            .and_then(|workspace| {
+                workspace
+                    .root()
+                    .inner()
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
+        let root = options.build_root.clone().unwrap_or(options.path.clone());
+        let build_path = options.output_dir();
+        // Only the objects of this build are measured, and none of the tests:
+        // not even those of the members that were not selected.
+        let mut objects = vec![];
+        let mut test_mains = vec![];
+        if options.coverage {
+            Coverage::clear(&build_path)?;
+            for built in built {
+                objects.extend(built.package.into_iter().map(PathBuf::from));
+                if self.coverage_deps {
+                    objects.extend(built.dependencies.into_iter().map(PathBuf::from));
+                }
+            }
+            let packages = match &workspace {
+                Some(workspace) => workspace.members(),
+                None => &members,
+            };
+            for package in packages {
+                test_mains.extend(
+                    Workspace::load(package)?
+                        .tests()
+                        .iter()
+                        .filter_map(|test| test.main().canonicalize().ok()),
+                );
+            }
+        }
+
+        let mut tests = vec![];
+        let mut filtered_out = 0;
+        let mut gcov = vec![];
+        for member in members {
+            let manifest = Workspace::load(&member)?;
+            options.path = member.clone();
+            if !qualified {
+                suite_name = manifest.name().to_string();
+            }
+
+            let mut profile = manifest.profile(&options.profile)?.clone();
+            profile.add_sanitizers(&options.sanitize);
+            let sanitizer_env = profile
+                .sanitizers()
+                .iter()
+                .filter_map(|sanitizer| sanitizer.runtime_options())
+                .filter(|(variable, _)| std::env::var_os(variable).is_none())
+                .collect::<Vec<_>>();
+            let toolchain = match &options.target {
+                Some(target) => Some(Toolchain::load(target, &member)?),
+                None => None,
+            };
+            let runner = toolchain
+                .as_ref()
+                .map(|toolchain| toolchain.runner().to_vec())
+                .unwrap_or_default();
+            if options.coverage && gcov.is_empty() {
+                let compiler = toolchain
+                    .as_ref()
+                    .map_or(manifest.compiler(), |toolchain| toolchain.cc())
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect::<Vec<_>>();
+                if compiler.is_empty() {
+                    return Err("the compiler command is empty".to_string());
+                }
+                gcov = CompilerFamily::detect(&compiler)?.gcov(&compiler)?;
+            }
+            let selected = selected_targets(&options, &manifest)?;
+            filtered_out += manifest.tests().len() - selected.len();
+            for test in selected {
+                let mut command_line = runner.clone();
+                command_line.push(
+                    test.output_path(&options.output_dir(), &manifest)
+                        .to_string_lossy()
+                        .to_string(),
+                );
+                let mut command = std::process::Command::new(&command_line[0]);
+                command
+                    .args(&command_line[1..])
+                    .current_dir(member.inner())
+                    .envs(sanitizer_env.clone());
+
+                let name = match qualified {
+                    true => format!("{}/{}", manifest.name(), test.name()),
+                    false => test.name().to_string(),
+                };
+                tests.push((name, command));
+            }
+        }
+
+        self.report(&format!(
+            "{} {} test{}",
+            success("Running"),
+            tests.len(),
+            if tests.len() != 1 { "s" } else { "" }
+        ));
+        let mut results: Vec<TestResult> = vec![];
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT));
+        TestRunner::new(options.jobs, timeout).run(tests, |result| {
+            self.report(&format!(
+                "test {} ... {} ({:.2}s)",
+                result.name,
+                match &result.outcome {
+                    Outcome::Passed => "ok",
+                    Outcome::Failed(_) => "FAILED",
+                    Outcome::TimedOut => "TIMEOUT",
+                },
+                result.duration.as_secs_f32()
+            ));
+            results.push(result);
+        });
+
+        results.sort_by(|a, b| a.name.cmp(&b.name));
+        if self.format != ReportFormat::Human {
+            let report = TestReport::new(&suite_name, &results, timeout).render(self.format);
+            match &self.output {
+                Some(output) => std::fs::write(output, report)
+                    .map_err(|e| format!("failed to write {}: {}", output, e))?,
+                None => print!("{}", report),
+            }
+        }
+
+        let failures = results
+            .iter()
+            .filter(|result| !result.passed())
+            .collect::<Vec<_>>();
+        if !failures.is_empty() {
+            self.report("\nfailures:");
+            for failure in &failures {
+                let reason = match &failure.outcome {
+                    Outcome::Failed(reason) => reason.clone(),
+                    _ => format!("timed out after {}s", timeout.as_secs()),
+                };
+                self.report(&format!("\n---- {} ({}) ----", failure.name, reason));
+                for output in [&failure.stdout, &failure.stderr] {
+                    if !output.is_empty() {
+                        self.report(output.trim_end());
+                    }
+                }
+            }
+        }
+
+        self.report(&format!(
+            "\ntest result: {}. {} passed; {} failed; {} filtered out; finished in {:.2}s",
+            match failures.is_empty() {
+                true => "ok",
+                false => "FAILED",
+            },
+            results.len() - failures.len(),
+            failures.len(),
+            filtered_out,
+            start.elapsed().as_secs_f32()
+        ));
+
+        if options.coverage {
+            self.report_coverage(
+                &gcov,
+                &build_path,
+                root.inner(),
+                &suite_name,
+                &objects,
+                &test_mains,
+            )?;
+        }
+
+        match failures.len() {
+            0 => Ok(()),
+            1 => Err("1 test failed".to_string()),
+            failed => Err(format!("{} tests failed", failed)),
+        }
+    }
+}
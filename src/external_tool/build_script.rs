@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+
+/// Directives printed by a package's build script, one per line on stdout:
+///
+/// ```text
+/// tailor:source=<path>            compile an extra (usually generated) source
+/// tailor:include=<dir>            add an include directory, also seen by dependents
+/// tailor:define=<KEY>[=<value>]   add a preprocessor define
+/// tailor:link=<lib>               link with `-l<lib>`
+/// tailor:rerun-if-changed=<path>  run the script again when this file changes
+/// ```
+///
+/// Relative paths are resolved against the package directory. Any other line
+/// is ignored, so scripts can print progress freely.
+#[derive(Clone, Default)]
+pub struct BuildScriptOutput {
+    pub sources: Vec<String>,
+    pub includes: Vec<String>,
+    pub defines: Vec<String>,
+    pub links: Vec<String>,
+    pub rerun_if_changed: Vec<PathBuf>,
+}
+
+impl BuildScriptOutput {
+    pub fn parse(stdout: &str, package_dir: &Path) -> Result<Self, String> {
+        let mut output = Self::default();
+        let resolve = |path: &str| package_dir.join(path);
+
+        for line in stdout.lines() {
+            let Some(directive) = line.trim_end().strip_prefix("tailor:") else {
+                continue;
+            };
+            let Some((key, value)) = directive.split_once('=') else {
+                return Err(format!("invalid build script directive `{}`", line));
+            };
+            if value.is_empty() {
+                return Err(format!("build script directive `{}` has no value", line));
+            }
+
+            match key {
+                "source" => output
+                    .sources
+                    .push(resolve(value).to_string_lossy().to_string()),
+                "include" => output
+                    .includes
+                    .push(resolve(value).to_string_lossy().to_string()),
+                "define" => output.defines.push(format!("-D{}", value)),
+                "link" => output.links.push(format!("-l{}", value)),
+                "rerun-if-changed" => output.rerun_if_changed.push(resolve(value)),
+                _ => return Err(format!("unknown build script directive `tailor:{}`", key)),
+            }
+        }
+
+        Ok(output)
+    }
+}
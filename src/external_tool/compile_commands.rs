@@ -6,14 +6,27 @@ pub struct CompileCommandEntry {
     directory: PathBuf,
     arguments: Vec<String>,
     file: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
 }
 
 impl CompileCommandEntry {
-    pub fn new(directory: PathBuf, arguments: Vec<String>, file: PathBuf) -> Self {
+    pub fn new(
+        directory: PathBuf,
+        arguments: Vec<String>,
+        file: PathBuf,
+        target: Option<String>,
+    ) -> Self {
         Self {
             directory,
             arguments,
             file,
+            target,
         }
     }
+
+    /// The compiled source file.
+    pub fn path(&self) -> PathBuf {
+        self.directory.join(&self.file)
+    }
 }
@@ -1,79 +1,418 @@
 use crate::{
-    external_tool::compile_commands::CompileCommandEntry,
-    fmt::{Progress, success},
-    manifest::package_type::PackageType,
-    mode::Mode,
+    external_tool::{
+        build_script::BuildScriptOutput,
+        compile_commands::CompileCommandEntry,
+        compiler_family::CompilerFamily,
+        config_header::ConfigHeader,
+        depfile::DepFile,
+        diagnostic::{Diagnostic, MessageFormat, Severity},
+        job_pool::{JobEvent, JobPool},
+        test_cases::TestHeader,
+    },
+    fmt::{Progress, error, success, warning},
+    manifest::{
+        Manifest, features::Features, package_type::PackageType, profile::Profile, target::Target,
+    },
     package::Package,
+    toolchain::Toolchain,
 };
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+
+/// The objects of a build: the package's own, and those of its dependencies.
+pub struct BuiltObjects {
+    pub package: Vec<String>,
+    pub dependencies: Vec<String>,
+}
 
 pub struct Compiler {
-    compiler: String,
+    compiler: Vec<String>,
+    archiver: Vec<String>,
+    family: CompilerFamily,
+    host_compiler: Vec<String>,
+    host_family: CompilerFamily,
+    toolchain: Option<Toolchain>,
     pkg_full_name: String,
+    jobs: usize,
+    message_format: MessageFormat,
+    /// Status lines go to stderr, as stdout carries JSON messages or a report.
+    quiet: bool,
+    verbose: bool,
 }
 
 impl Compiler {
-    pub fn new(compiler: &str, pkg_full_name: String) -> Self {
-        Self {
-            compiler: compiler.to_string(),
+    /// `compiler` is split on whitespace into the program and its leading
+    /// arguments (e.g. `"gcc -m32"`); it is never interpreted by a shell.
+    ///
+    /// When cross-compiling, the `toolchain` compiler and archiver replace
+    /// `compiler` and `ar`; `compiler` is still used for build scripts.
+    pub fn new(
+        compiler: &str,
+        pkg_full_name: String,
+        jobs: usize,
+        toolchain: Option<Toolchain>,
+        message_format: MessageFormat,
+        stdout_reserved: bool,
+        verbose: bool,
+    ) -> Result<Self, String> {
+        let host_compiler = compiler;
+        let (compiler, archiver) = match &toolchain {
+            Some(toolchain) => (toolchain.cc(), toolchain.ar()),
+            None => (compiler, "ar"),
+        };
+        let split = |command: &str, what: &str| -> Result<Vec<String>, String> {
+            let command = command
+                .split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>();
+            match command.is_empty() {
+                true => Err(format!("the {} command is empty", what)),
+                false => Ok(command),
+            }
+        };
+
+        let compiler = split(compiler, "compiler")?;
+        let family = CompilerFamily::detect(&compiler)?;
+        // Build scripts run on the build machine, so they use the package's compiler.
+        let host_compiler = split(host_compiler, "compiler")?;
+        let host_family = match toolchain {
+            Some(_) => CompilerFamily::detect(&host_compiler)?,
+            None => family.clone(),
+        };
+
+        Ok(Self {
+            compiler,
+            archiver: split(archiver, "archiver")?,
+            family,
+            host_compiler,
+            host_family,
+            toolchain,
             pkg_full_name,
+            jobs,
+            message_format,
+            quiet: stdout_reserved || message_format == MessageFormat::Json,
+            verbose,
+        })
+    }
+
+    /// Spawns `args[0]` with the remaining arguments, without going through a shell.
+    fn command(args: &[String]) -> std::process::Command {
+        let mut command = std::process::Command::new(&args[0]);
+        command.args(&args[1..]);
+        command
+    }
+
+    /// Prints a status line, on stderr when stdout carries JSON messages or a report.
+    fn status(&self, message: &str) {
+        match self.quiet {
+            false => println!("{}", message),
+            true => eprintln!("{}", message),
         }
     }
 
-    fn get_object_path(source: &str, build_path: &Path) -> Result<String, String> {
-        let source_path = Path::new(source);
-        let file_stem = source_path
-            .file_stem()
-            .ok_or_else(|| "invalid source file".to_string())?
-            .to_string_lossy();
-        let object_path = build_path.join(format!("{}.o", file_stem));
+    /// Reports an artifact of `package` with `--message-format json`; `fresh`
+    /// artifacts were up to date and not produced again.
+    fn artifact(&self, package: &str, kind: &str, path: &str, fresh: bool) {
+        if self.message_format == MessageFormat::Json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "reason": "compiler-artifact",
+                    "package": package,
+                    "kind": kind,
+                    "path": path,
+                    "fresh": fresh,
+                })
+            );
+        }
+    }
+
+    /// Prints the diagnostics of a build, grouped by package and then by file.
+    fn report(diagnostics: &[(String, Diagnostic)]) {
+        let mut packages: Vec<&str> = vec![];
+        for (package, _) in diagnostics {
+            if !packages.contains(&package.as_str()) {
+                packages.push(package);
+            }
+        }
+
+        for package in packages {
+            let diagnostics = diagnostics
+                .iter()
+                .filter(|(p, _)| p == package)
+                .map(|(_, diagnostic)| diagnostic)
+                .collect::<Vec<_>>();
+            let count = |severity| {
+                diagnostics
+                    .iter()
+                    .filter(|diagnostic| diagnostic.severity == severity)
+                    .count()
+            };
+            let plural = |count: usize, what: &str| match count {
+                1 => format!("1 {}", what),
+                _ => format!("{} {}s", count, what),
+            };
+            let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+            let summary = match (errors, warnings) {
+                (0, 0) => "notes".to_string(),
+                (0, _) => plural(warnings, "warning"),
+                (_, 0) => plural(errors, "error"),
+                (_, _) => format!(
+                    "{} and {}",
+                    plural(errors, "error"),
+                    plural(warnings, "warning")
+                ),
+            };
+            eprintln!(
+                "{}: {} generated {}",
+                if errors > 0 { error() } else { warning() },
+                package,
+                summary
+            );
+
+            let mut files: Vec<&str> = vec![];
+            for diagnostic in &diagnostics {
+                if !files.contains(&diagnostic.file.as_str()) {
+                    files.push(&diagnostic.file);
+                }
+            }
+            for file in files {
+                eprintln!("  --> {}", file);
+                for diagnostic in diagnostics.iter().filter(|d| d.file == file) {
+                    eprintln!(
+                        "      {} {}: {}",
+                        diagnostic.position(),
+                        diagnostic.severity,
+                        diagnostic.message
+                    );
+                }
+            }
+        }
+    }
+
+    /// One argument per line, as recorded next to artifacts to detect flag changes.
+    ///
+    /// The detected compiler version is part of it, so upgrading the compiler
+    /// rebuilds everything.
+    fn command_line(&self, args: &[String]) -> String {
+        format!("{}\n{}", self.family.fingerprint(), args.join("\n"))
+    }
+
+    /// Maps a source of `manifest` to `<build>/obj/<name>@<version>/<relative source>.o`.
+    ///
+    /// The layout mirrors the package's source tree, so sources sharing a file
+    /// stem never collide, whether they live in different packages or in
+    /// different directories of the same package. Sources outside the package
+    /// directory are placed under `external/`, keyed by their absolute path.
+    fn get_object_path(
+        source: &str,
+        build_path: &Path,
+        manifest: &Manifest,
+    ) -> Result<String, String> {
+        let source_path = Path::new(source)
+            .canonicalize()
+            .map_err(|e| format!("invalid source file {}: {}", source, e))?;
+
+        let relative_path = match source_path.strip_prefix(manifest.location().inner()) {
+            Ok(relative_path) => relative_path.to_path_buf(),
+            Err(_) => Path::new("external").join(
+                source_path
+                    .components()
+                    .filter(|c| matches!(c, Component::Normal(_)))
+                    .collect::<PathBuf>(),
+            ),
+        };
+
+        let object_path = build_path
+            .join("obj")
+            .join(manifest.full_name())
+            .join(relative_path)
+            .with_extension("o");
+
+        if let Some(parent) = object_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("fail to create object directory: {}", e))?;
+        }
+
         Ok(object_path.to_string_lossy().to_string())
     }
 
+    /// Path of a sidecar file next to `artifact`, e.g. `util.o` -> `util.o.d`.
+    fn sidecar_path(artifact: &str, extension: &str) -> String {
+        format!("{}.{}", artifact, extension)
+    }
+
+    /// Checks whether `artifact` must be produced again by `command`.
+    ///
+    /// The artifact is stale when it is missing, when the command line recorded
+    /// for it differs from `command`, or when one of its prerequisites is newer
+    /// than the artifact itself.
+    fn is_stale(artifact: &str, command: &str, prerequisites: &DepFile) -> bool {
+        let Ok(modified) = std::fs::metadata(artifact).and_then(|m| m.modified()) else {
+            return true;
+        };
+
+        let cmd_path = Self::sidecar_path(artifact, "cmd");
+        match std::fs::read_to_string(cmd_path) {
+            Ok(recorded) if recorded == command => {}
+            _ => return true,
+        }
+
+        prerequisites.is_newer_than(modified)
+    }
+
+    fn record_command(artifact: &str, command: &str) -> Result<(), String> {
+        std::fs::write(Self::sidecar_path(artifact, "cmd"), command)
+            .map_err(|e| format!("failed to record command for {}: {}", artifact, e))
+    }
+
+    /// Builds `package` and `targets` into `build_path`, returning the objects
+    /// compiled, or reused, for them.
     pub fn build(
         &self,
-        mode: Mode,
+        profile: &Profile,
         build_path: &Path,
         package: Package,
         pkg_type: PackageType,
-        defines: Vec<String>,
-    ) -> Result<(), String> {
+        targets: &[Target],
+    ) -> Result<BuiltObjects, String> {
         let mut dependencies = package.dependencies().to_vec();
         dependencies.push(package.manifest().clone());
 
-        let mut object_list = vec![];
+        // Dependencies' scripts run first, as a package's script may rely on them.
+        // A config header's directory is handled like a generated include one.
+        let mut generated = vec![];
+        for dependency in dependencies.iter().rev() {
+            let mut output = self.run_build_script(profile, build_path, dependency)?;
+            if dependency.config_header() {
+                let directory = ConfigHeader::write(build_path, dependency)?;
+                output
+                    .get_or_insert_default()
+                    .includes
+                    .push(directory.to_string_lossy().to_string());
+            }
+            if let Some(output) = output {
+                generated.push((dependency.name().to_string(), output));
+            }
+        }
+        let generated_by = |name: &str| {
+            generated
+                .iter()
+                .find(|(package, _)| package == name)
+                .map(|(_, output)| output)
+        };
+
+        let pic = pkg_type == PackageType::Library && package.manifest().lib_kind().is_shared();
+        let mut objects_by_package = vec![];
         let mut compile_command_entries = vec![];
+        let mut stale_jobs = vec![];
 
-        let mut progress = Progress::new("Building", dependencies.len());
-        for dependency in dependencies {
-            let message = format!(
-                "{} {} v{}",
-                success("Compiling"),
-                dependency.name(),
-                dependency.version()
-            );
+        let root_name = package.manifest().full_name();
+        let root_location = package.manifest().location().clone();
+        let packages = dependencies.clone();
+        for mut dependency in dependencies {
+            let header_only_defines = Self::header_only_defines(&dependency, &packages);
+            // Like cargo's `cap-lints`: dependencies' warnings are noise unless asked for.
+            let warning_flags = match (dependency.full_name() == root_name, self.verbose) {
+                (true, _) => self.family.warning_flags(dependency.lints(), false)?,
+                (false, true) => self.family.warning_flags(dependency.lints(), true)?,
+                (false, false) => vec!["-w".to_string()],
+            };
+            let own = generated_by(dependency.name()).cloned().unwrap_or_default();
+            // Like include directories, generated ones are visible to direct dependents.
+            let mut generated_includes = own.includes.clone();
+            for direct in dependency.dependencies() {
+                if let Some(output) = generated_by(direct.name()) {
+                    generated_includes.extend(output.includes.iter().cloned());
+                }
+            }
+            if !own.links.is_empty() {
+                let mut libs = own.links.clone();
+                libs.extend(dependency.system_libs().iter().cloned());
+                dependency.set_system_flags(dependency.system_cflags().to_vec(), libs);
+            }
 
-            for source in dependency.sources() {
-                let object_path = Self::get_object_path(&source, build_path)?;
+            let package_sources = dependency
+                .sources()
+                .into_iter()
+                .chain(own.sources.clone())
+                .collect::<Vec<_>>();
+            // The root package's binaries and examples are compiled along with it.
+            let mut target_sources = vec![];
+            let mut dev_sources = vec![];
+            if dependency.full_name() == root_name {
+                for target in targets {
+                    for source in dependency.target_sources(target) {
+                        if target.is_dev() && !dev_sources.contains(&source) {
+                            dev_sources.push(source.clone());
+                        }
+                        if !package_sources.contains(&source) && !target_sources.contains(&source) {
+                            target_sources.push(source);
+                        }
+                    }
+                }
+            }
+            // Tests and examples also see the headers of the dev-dependencies.
+            let mut dev_includes = dependency
+                .dev_includes()
+                .iter()
+                .map(|inc| inc.to_string())
+                .collect::<Vec<_>>();
+            for dev_dependency in dependency.dev_dependencies() {
+                if let Some(output) = generated_by(dev_dependency.name()) {
+                    dev_includes.extend(output.includes.iter().cloned());
+                }
+            }
+            if !dev_sources.is_empty() {
+                let directory = TestHeader::write(build_path)?;
+                dev_includes.push(directory.to_string_lossy().to_string());
+            }
 
-                object_list.push(object_path.clone());
+            let mut object_list = vec![];
+            for source in package_sources.iter().chain(&target_sources).cloned() {
+                let object_path = Self::get_object_path(&source, build_path, &dependency)?;
+                let depfile_path = Self::sidecar_path(&object_path, "d");
 
-                let include_list = dependency
-                    .includes()
-                    .iter()
-                    .map(|inc| format!("-I{}", inc))
-                    .collect::<Vec<_>>()
-                    .join(" ");
-
-                let define_list = defines.join(" ");
-                let opt_level = match mode {
-                    Mode::Debug => "-Og",
-                    Mode::Release => "-Os",
-                };
-                let compile_cmd = format!(
-                    "{} -c {} {} {} {} -o {}",
-                    self.compiler, source, opt_level, define_list, include_list, object_path
+                if package_sources.contains(&source) {
+                    object_list.push(object_path.clone());
+                }
+
+                let mut arguments = self.compiler.clone();
+                arguments.extend(["-c".to_string(), source.clone()]);
+                if let Some(toolchain) = &self.toolchain {
+                    arguments.extend(toolchain.compile_flags());
+                }
+                arguments.extend(self.family.compile_flags(profile)?);
+                arguments.extend(profile.cflags().iter().cloned());
+                arguments.extend(
+                    profile
+                        .defines()
+                        .iter()
+                        .map(|define| define.to_define(false))
+                        .filter(|define| !define.is_empty()),
                 );
+                arguments.extend(warning_flags.iter().cloned());
+                if pic {
+                    arguments.push("-fPIC".to_string());
+                }
+                // Package flags come after the profile's so they take precedence.
+                if let Some(std) = dependency.std() {
+                    arguments.push(format!("-std={}", std));
+                }
+                arguments.extend(dependency.system_cflags().iter().cloned());
+                arguments.extend(dependency.cflags().iter().cloned());
+                arguments.extend(Self::package_defines(&dependency));
+                arguments.extend(header_only_defines.iter().cloned());
+                arguments.extend(own.defines.iter().cloned());
+                arguments.extend(dependency.includes().iter().map(|inc| format!("-I{}", inc)));
+                arguments.extend(generated_includes.iter().map(|inc| format!("-I{}", inc)));
+                if dev_sources.contains(&source) && !package_sources.contains(&source) {
+                    arguments.extend(dev_includes.iter().map(|inc| format!("-I{}", inc)));
+                }
+                arguments.extend(["-o".to_string(), object_path.clone()]);
+
+                let mut compile_args = arguments.clone();
+                compile_args.extend(self.family.depfile_flags(&depfile_path));
+                let compile_cmd = self.command_line(&compile_args);
 
                 let source_path = Path::new(&source);
                 let source_dir = source_path.parent().unwrap_or_else(|| Path::new("."));
@@ -81,72 +420,638 @@ impl Compiler {
                     .file_name()
                     .unwrap_or_else(|| std::ffi::OsStr::new(""))
                     .into();
-                let arguments = vec![
-                    self.compiler.clone(),
-                    "-c".to_string(),
-                    source.clone(),
-                    opt_level.to_string(),
-                    define_list,
-                    include_list,
-                    "-o".to_string(),
-                    object_path.clone(),
-                ];
-                let compile_command_entry =
-                    CompileCommandEntry::new(source_dir.to_owned(), arguments, source_file);
+                let compile_command_entry = CompileCommandEntry::new(
+                    source_dir.to_owned(),
+                    arguments,
+                    source_file,
+                    self.toolchain.as_ref().map(|t| t.name().to_string()),
+                );
                 compile_command_entries.push(compile_command_entry);
 
-                let status = std::process::Command::new("sh")
-                    .arg("-c")
-                    .arg(compile_cmd)
-                    .status()
-                    .map_err(|e| format!("failed to execute compile command: {}", e))?;
-                if !status.success() {
-                    return Err(format!(
-                        "compilation failed for dependency source: {}",
-                        source
-                    ));
+                let stale = match DepFile::from_file(Path::new(&depfile_path)) {
+                    Ok(depfile) => Self::is_stale(&object_path, &compile_cmd, &depfile),
+                    Err(_) => true,
+                };
+                if !stale {
+                    self.artifact(&dependency.full_name(), "object", &object_path, true);
+                } else {
+                    stale_jobs.push(CompileJob {
+                        package: dependency.full_name(),
+                        version: dependency.version().to_string(),
+                        name: dependency.name().to_string(),
+                        source,
+                        object: object_path,
+                        args: compile_args,
+                    });
                 }
             }
-
-            progress.next(&message);
+            objects_by_package.push((dependency, object_list));
         }
 
+        // Members of a workspace share the build directory: keep the entries of
+        // the other members, and of dependencies not compiled this time.
+        let compile_commands_path = build_path.join("compile_commands.json");
+        if let Ok(previous) = std::fs::read_to_string(&compile_commands_path)
+            && let Ok(previous) = serde_json::from_str::<Vec<CompileCommandEntry>>(&previous)
+        {
+            let kept = previous
+                .into_iter()
+                .filter(|entry| {
+                    !entry.path().starts_with(root_location.inner())
+                        && !compile_command_entries
+                            .iter()
+                            .any(|new: &CompileCommandEntry| new.path() == entry.path())
+                })
+                .collect::<Vec<_>>();
+            compile_command_entries.splice(0..0, kept);
+        }
         let compile_commands_json = serde_json::to_string_pretty(&compile_command_entries)
             .map_err(|e| format!("failed to serialize compile commands: {}", e))?;
-        std::fs::write(
-            build_path.join("compile_commands.json"),
-            compile_commands_json,
-        )
-        .map_err(|e| format!("failed to write compile_commands.json: {}", e))?;
-
-        let link_cmd = match pkg_type {
-            PackageType::Binary => format!(
-                "{} {} -o {}",
-                self.compiler,
-                object_list.join(" "),
-                build_path.join(&self.pkg_full_name).to_string_lossy()
-            ),
-            PackageType::Library => format!(
-                "{} -shared {} -o {}",
-                self.compiler,
-                object_list.join(" "),
-                build_path
-                    .join(format!("lib{}.so", self.pkg_full_name))
-                    .to_string_lossy()
+        std::fs::write(&compile_commands_path, compile_commands_json)
+            .map_err(|e| format!("failed to write compile_commands.json: {}", e))?;
+
+        let mut progress = match self.quiet {
+            false => Progress::new("Building", stale_jobs.len()),
+            true => Progress::quiet("Building", stale_jobs.len()),
+        };
+        let mut diagnostics = vec![];
+        let compiled = self.compile(&stale_jobs, &mut progress, &mut diagnostics);
+        progress.finish();
+        if self.message_format == MessageFormat::Human {
+            Self::report(&diagnostics);
+        }
+        compiled?;
+
+        let (root, root_objects) = objects_by_package
+            .pop()
+            .ok_or_else(|| "missing root package".to_string())?;
+
+        // Dev-dependencies only go into tests and examples.
+        let mut archives = vec![];
+        let mut dev_archives = vec![];
+        for (dependency, objects) in &objects_by_package {
+            // Header-only packages have nothing to archive.
+            if objects.is_empty() {
+                continue;
+            }
+            let archive_path = build_path
+                .join("deps")
+                .join(dependency.full_name())
+                .join(format!("lib{}.a", dependency.name()));
+            self.archive(&dependency.full_name(), &archive_path, objects)?;
+            let archive_path = archive_path.to_string_lossy().to_string();
+            if !package.is_dev_only(dependency) {
+                archives.push(archive_path.clone());
+            }
+            dev_archives.push(archive_path);
+        }
+
+        // The final link uses the target's and profile's flags, then the root's,
+        // then those of every dependency.
+        let link_flags = |dev: bool| -> Result<Vec<String>, String> {
+            let linked = objects_by_package
+                .iter()
+                .map(|(dependency, _)| dependency)
+                .filter(|dependency| dev || !package.is_dev_only(dependency))
+                .collect::<Vec<_>>();
+
+            let mut ldflags = self
+                .toolchain
+                .as_ref()
+                .map(|toolchain| toolchain.link_flags())
+                .unwrap_or_default();
+            ldflags.extend(self.family.link_flags(profile)?);
+            ldflags.extend(profile.ldflags().iter().cloned());
+            ldflags.extend(root.ldflags().iter().cloned());
+            for dependency in &linked {
+                ldflags.extend(dependency.ldflags().iter().cloned());
+            }
+
+            // Libraries go last, in link order; keep the last occurrence of duplicates.
+            let mut libs = root.system_libs().to_vec();
+            for dependency in &linked {
+                libs.extend(dependency.system_libs().iter().cloned());
+            }
+            let mut unique_libs: Vec<String> = vec![];
+            for lib in libs.into_iter().rev() {
+                if !unique_libs.contains(&lib) {
+                    unique_libs.insert(0, lib);
+                }
+            }
+            ldflags.extend(unique_libs);
+
+            Ok(ldflags)
+        };
+        let ldflags = link_flags(false)?;
+
+        match pkg_type {
+            PackageType::Binary => {
+                let output_path = build_path.join(&self.pkg_full_name);
+                self.link(&output_path, &root_objects, &archives, &ldflags, false)?;
+                self.objcopy(&root.full_name(), &output_path)?;
+            }
+            PackageType::Library if root_objects.is_empty() => {
+                return Err(format!(
+                    "{} has no sources to build a library from; set `type = \"header-only\"` for a library made of headers only",
+                    root.full_name()
+                ));
+            }
+            PackageType::Library => {
+                if root.lib_kind().is_static() {
+                    let output_path = build_path.join(format!("lib{}.a", root.name()));
+                    self.archive(&root.full_name(), &output_path, &root_objects)?;
+                }
+                if root.lib_kind().is_shared() {
+                    let output_path = build_path.join(format!("lib{}.so", root.name()));
+                    self.link(&output_path, &root_objects, &archives, &ldflags, true)?;
+                }
+            }
+            // Only the dependencies' build scripts and compile_commands.json are produced.
+            PackageType::HeaderOnly => {}
+        }
+
+        // Other binaries and examples link the package's objects but its default `main`.
+        let main_object = root.main().and_then(|main| {
+            Self::get_object_path(&main.to_string_lossy(), build_path, &root).ok()
+        });
+        let library_objects = root_objects
+            .iter()
+            .filter(|object| Some(*object) != main_object.as_ref())
+            .cloned()
+            .collect::<Vec<_>>();
+        for target in targets {
+            let mut objects = library_objects.clone();
+            for source in root.target_sources(target) {
+                objects.push(Self::get_object_path(&source, build_path, &root)?);
+            }
+            let output_path = target.output_path(build_path, &root);
+            match target.is_dev() {
+                true => self.link(
+                    &output_path,
+                    &objects,
+                    &dev_archives,
+                    &link_flags(true)?,
+                    false,
+                )?,
+                false => self.link(&output_path, &objects, &archives, &ldflags, false)?,
+            }
+            self.objcopy(&root.full_name(), &output_path)?;
+        }
+
+        Ok(BuiltObjects {
+            package: root_objects,
+            dependencies: objects_by_package
+                .into_iter()
+                .flat_map(|(_, objects)| objects)
+                .collect(),
+        })
+    }
+
+    /// `-D` flags for the options and enabled features of a package, unless
+    /// they go to its config header.
+    fn package_defines(manifest: &Manifest) -> Vec<String> {
+        if manifest.config_header() {
+            return vec![];
+        }
+
+        let declared = manifest.options_schema().is_some();
+        let mut defines = manifest
+            .options()
+            .iter()
+            .map(|option| option.to_define(declared))
+            .filter(|define| !define.is_empty())
+            .collect::<Vec<_>>();
+        defines.extend(
+            manifest
+                .enabled_features()
+                .iter()
+                .map(|feature| Features::define(feature)),
+        );
+        defines
+    }
+
+    /// Defines of the header-only packages whose headers `manifest` compiles:
+    /// its header-only direct dependencies and, in turn, theirs.
+    fn header_only_defines(manifest: &Manifest, packages: &[Manifest]) -> Vec<String> {
+        let mut defines = vec![];
+        for dependency in manifest.dependencies() {
+            if let Some(package) = packages
+                .iter()
+                .find(|package| package.name() == dependency.name())
+                && package.is_header_only()
+            {
+                defines.extend(Self::package_defines(package));
+                defines.extend(Self::header_only_defines(package, packages));
+            }
+        }
+        defines
+    }
+
+    /// Runs the package's build script, if any, and returns its directives.
+    ///
+    /// A C script is first compiled for the build machine. The script runs in
+    /// the package directory with `TAILOR_*` variables describing the build
+    /// (including `TAILOR_FEATURE_<NAME>=1` for each enabled feature),
+    /// and only runs again when its command, the script (or a header it
+    /// includes), or a file named by `tailor:rerun-if-changed` changed;
+    /// otherwise the directives recorded by the last run are reused.
+    fn run_build_script(
+        &self,
+        profile: &Profile,
+        build_path: &Path,
+        manifest: &Manifest,
+    ) -> Result<Option<BuildScriptOutput>, String> {
+        let Some(script) = manifest.build_script() else {
+            return Ok(None);
+        };
+        if !script.is_file() {
+            return Err(format!(
+                "{}: build script {} not found",
+                manifest.full_name(),
+                script.display()
+            ));
+        }
+
+        let script_dir = build_path.join("scripts").join(manifest.full_name());
+        let out_dir = script_dir.join("out");
+        std::fs::create_dir_all(&out_dir)
+            .map_err(|e| format!("fail to create build script directory: {}", e))?;
+
+        let executable = match script.extension().is_some_and(|ext| ext == "c") {
+            true => self.compile_build_script(script, &script_dir)?,
+            false => script.to_path_buf(),
+        };
+
+        let mut env = vec![
+            ("TAILOR_OUT_DIR", out_dir.to_string_lossy().to_string()),
+            ("TAILOR_PKG_NAME", manifest.name().to_string()),
+            ("TAILOR_PKG_VERSION", manifest.version().to_string()),
+            (
+                "TAILOR_MANIFEST_DIR",
+                manifest.location().inner().to_string_lossy().to_string(),
             ),
+            ("TAILOR_PROFILE", profile.name().to_string()),
+            ("TAILOR_CC", self.compiler.join(" ")),
+        ];
+        if let Some(toolchain) = &self.toolchain {
+            env.push(("TAILOR_TARGET", toolchain.name().to_string()));
+        }
+        let feature_variables = manifest
+            .enabled_features()
+            .iter()
+            .map(|feature| Features::variable(feature))
+            .collect::<Vec<_>>();
+        env.extend(
+            feature_variables
+                .iter()
+                .map(|variable| (variable.as_str(), "1".to_string())),
+        );
+
+        let mut run_args = vec![executable.to_string_lossy().to_string()];
+        run_args.extend(env.iter().map(|(key, value)| format!("{}={}", key, value)));
+        let run_cmd = self.command_line(&run_args);
+
+        let recorded = script_dir.join("output");
+        let recorded_path = recorded.to_string_lossy().to_string();
+        if let Ok(stdout) = std::fs::read_to_string(&recorded)
+            && let Ok(output) = BuildScriptOutput::parse(&stdout, manifest.location().inner())
+        {
+            let mut prerequisites = vec![executable.clone()];
+            prerequisites.extend(output.rerun_if_changed.iter().cloned());
+            if !Self::is_stale(&recorded_path, &run_cmd, &DepFile::new(prerequisites)) {
+                return Ok(Some(output));
+            }
+        }
+
+        self.status(&format!(
+            "{} {} v{} (build script)",
+            success("Running"),
+            manifest.name(),
+            manifest.version()
+        ));
+        let output = std::process::Command::new(&executable)
+            .current_dir(manifest.location().inner())
+            .envs(env)
+            .output()
+            .map_err(|e| format!("failed to run build script {}: {}", script.display(), e))?;
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        if !output.status.success() {
+            return Err(format!(
+                "build script of {} failed ({}):\n{}{}",
+                manifest.full_name(),
+                output.status,
+                stdout,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let directives = BuildScriptOutput::parse(&stdout, manifest.location().inner())
+            .map_err(|e| format!("build script of {}: {}", manifest.full_name(), e))?;
+        std::fs::write(&recorded, &stdout)
+            .map_err(|e| format!("failed to record build script output: {}", e))?;
+        Self::record_command(&recorded_path, &run_cmd)?;
+
+        Ok(Some(directives))
+    }
+
+    /// Compiles a C build script with the host compiler into `<script_dir>/build`.
+    fn compile_build_script(&self, script: &Path, script_dir: &Path) -> Result<PathBuf, String> {
+        let executable = script_dir.join("build");
+        let executable_path = executable.to_string_lossy().to_string();
+        let depfile_path = Self::sidecar_path(&executable_path, "d");
+
+        let mut args = self.host_compiler.clone();
+        args.extend([
+            script.to_string_lossy().to_string(),
+            "-o".to_string(),
+            executable_path.clone(),
+        ]);
+        args.extend(self.host_family.depfile_flags(&depfile_path));
+        let command = format!("{}\n{}", self.host_family.fingerprint(), args.join("\n"));
+
+        let stale = match DepFile::from_file(Path::new(&depfile_path)) {
+            Ok(depfile) => Self::is_stale(&executable_path, &command, &depfile),
+            Err(_) => true,
+        };
+        if !stale {
+            return Ok(executable);
+        }
+
+        let output = Self::command(&args)
+            .output()
+            .map_err(|e| format!("failed to compile build script: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "failed to compile build script {}:\n{}",
+                script.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Self::record_command(&executable_path, &command)?;
+
+        Ok(executable)
+    }
+
+    /// Bundles `objects` into a static archive with `ar`.
+    fn archive(
+        &self,
+        package: &str,
+        archive_path: &Path,
+        objects: &[String],
+    ) -> Result<(), String> {
+        let archive = archive_path.to_string_lossy().to_string();
+        let mut archive_args = self.archiver.clone();
+        archive_args.extend(["rcs".to_string(), archive.clone()]);
+        archive_args.extend(objects.iter().cloned());
+        let archive_cmd = self.command_line(&archive_args);
+        let prerequisites = DepFile::new(objects.iter().map(PathBuf::from).collect());
+
+        if !Self::is_stale(&archive, &archive_cmd, &prerequisites) {
+            self.artifact(package, "archive", &archive, true);
+            return Ok(());
+        }
+
+        if let Some(parent) = archive_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("fail to create archive directory: {}", e))?;
+        }
+
+        // `ar` only adds and replaces members, so start over to drop removed sources.
+        if archive_path.exists() {
+            std::fs::remove_file(archive_path)
+                .map_err(|e| format!("failed to remove old archive {}: {}", archive, e))?;
+        }
+
+        let status = Self::command(&archive_args)
+            .status()
+            .map_err(|e| format!("failed to execute archive command: {}", e))?;
+        if !status.success() {
+            return Err(format!("archiving failed: {}", archive));
+        }
+
+        Self::record_command(&archive, &archive_cmd)?;
+        self.artifact(package, "archive", &archive, false);
+
+        Ok(())
+    }
+
+    /// Converts a linked executable to raw binary (`.bin`) and Intel HEX (`.hex`)
+    /// images when the target toolchain provides `objcopy`.
+    fn objcopy(&self, package: &str, executable: &Path) -> Result<(), String> {
+        let Some(objcopy) = self.toolchain.as_ref().and_then(|t| t.objcopy()) else {
+            return Ok(());
         };
 
-        let status = std::process::Command::new("sh")
-            .arg("-c")
-            .arg(link_cmd)
+        let input = executable.to_string_lossy().to_string();
+        for (format, extension) in [("binary", "bin"), ("ihex", "hex")] {
+            let output = format!("{}.{}", input, extension);
+            let mut args = objcopy
+                .split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>();
+            args.extend([
+                "-O".to_string(),
+                format.to_string(),
+                input.clone(),
+                output.clone(),
+            ]);
+            let objcopy_cmd = self.command_line(&args);
+
+            if !Self::is_stale(
+                &output,
+                &objcopy_cmd,
+                &DepFile::new(vec![executable.into()]),
+            ) {
+                self.artifact(package, "image", &output, true);
+                continue;
+            }
+
+            let status = Self::command(&args)
+                .status()
+                .map_err(|e| format!("failed to execute objcopy: {}", e))?;
+            if !status.success() {
+                return Err(format!("objcopy failed: {}", output));
+            }
+
+            Self::record_command(&output, &objcopy_cmd)?;
+            self.artifact(package, "image", &output, false);
+        }
+
+        Ok(())
+    }
+
+    /// Links `objects` and dependency `archives` into an executable or shared library.
+    ///
+    /// `archives` must be in link order: every archive before the ones it depends on.
+    fn link(
+        &self,
+        output_path: &Path,
+        objects: &[String],
+        archives: &[String],
+        ldflags: &[String],
+        shared: bool,
+    ) -> Result<(), String> {
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("fail to create {}: {}", parent.display(), e))?;
+        }
+        let output = output_path.to_string_lossy().to_string();
+        let mut link_args = self.compiler.clone();
+        if shared {
+            link_args.push("-shared".to_string());
+        }
+        link_args.extend(objects.iter().cloned());
+        link_args.extend(archives.iter().cloned());
+        link_args.extend(ldflags.iter().cloned());
+        link_args.extend(["-o".to_string(), output.clone()]);
+        let link_cmd = self.command_line(&link_args);
+        let prerequisites = DepFile::new(
+            objects
+                .iter()
+                .chain(archives.iter())
+                .map(PathBuf::from)
+                .collect(),
+        );
+
+        let kind = match shared {
+            true => "shared-library",
+            false => "executable",
+        };
+        if !Self::is_stale(&output, &link_cmd, &prerequisites) {
+            self.artifact(&self.pkg_full_name, kind, &output, true);
+            return Ok(());
+        }
+
+        let status = Self::command(&link_args)
             .status()
             .map_err(|e| format!("failed to execute link command: {}", e))?;
         if !status.success() {
             return Err("linking failed".to_string());
         }
 
-        progress.finish();
+        Self::record_command(&output, &link_cmd)?;
+        self.artifact(&self.pkg_full_name, kind, &output, false);
 
         Ok(())
     }
+
+    /// Compiles `jobs` on the job pool, printing each package once when its first job starts.
+    ///
+    /// Compiler output is parsed into `diagnostics`, keyed by package, or
+    /// printed as JSON messages right away with `--message-format json`.
+    /// Output that contains no recognizable diagnostic is printed as is.
+    fn compile(
+        &self,
+        jobs: &[CompileJob],
+        progress: &mut Progress,
+        diagnostics: &mut Vec<(String, Diagnostic)>,
+    ) -> Result<(), String> {
+        let commands = jobs
+            .iter()
+            .map(|job| Self::command(&job.args))
+            .collect::<Vec<_>>();
+
+        let mut announced: Vec<&str> = vec![];
+        let mut failures = vec![];
+
+        JobPool::new(self.jobs).run(commands, |event| match event {
+            JobEvent::Started(id) => {
+                let job = &jobs[id];
+                if !announced.contains(&job.package.as_str()) {
+                    announced.push(&job.package);
+                    progress.println(&format!(
+                        "{} {} v{}",
+                        success("Compiling"),
+                        job.name,
+                        job.version
+                    ));
+                }
+                progress.start(&job.label());
+
+                true
+            }
+            JobEvent::Finished(id, output) => {
+                let job = &jobs[id];
+                progress.done(&job.label());
+
+                let output = match output {
+                    Ok(output) => output,
+                    Err(err) => {
+                        failures.push(err);
+                        return false;
+                    }
+                };
+
+                let mut captured = String::from_utf8_lossy(&output.stdout).to_string();
+                captured.push_str(&String::from_utf8_lossy(&output.stderr));
+                let parsed = Diagnostic::parse(&captured);
+                let unparsed = Diagnostic::unparsed(&captured);
+                if parsed.is_empty() && !captured.is_empty() {
+                    progress.eprint(&captured);
+                } else if !unparsed.is_empty() {
+                    // Summarized diagnostics would otherwise hide what does not parse.
+                    progress.eprint(&(unparsed.join("\n") + "\n"));
+                }
+                for diagnostic in parsed {
+                    match self.message_format {
+                        MessageFormat::Human => {
+                            diagnostics.push((format!("{} v{}", job.name, job.version), diagnostic))
+                        }
+                        MessageFormat::Json => {
+                            let mut message = serde_json::json!({
+                                "reason": "compiler-message",
+                                "package": job.package,
+                                "source": job.source,
+                            });
+                            if let (Some(message), Ok(serde_json::Value::Object(fields))) =
+                                (message.as_object_mut(), serde_json::to_value(&diagnostic))
+                            {
+                                message.extend(fields);
+                            }
+                            println!("{}", message);
+                        }
+                    }
+                }
+
+                if !output.status.success() {
+                    failures.push(format!(
+                        "could not compile `{}` ({} v{})",
+                        job.source, job.name, job.version
+                    ));
+                    return false;
+                }
+
+                if let Err(err) = Self::record_command(&job.object, &self.command_line(&job.args)) {
+                    failures.push(err);
+                    return false;
+                }
+                self.artifact(&job.package, "object", &job.object, false);
+
+                true
+            }
+        });
+
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(failures.join("\n")),
+        }
+    }
+}
+
+struct CompileJob {
+    package: String,
+    name: String,
+    version: String,
+    source: String,
+    object: String,
+    args: Vec<String>,
+}
+
+impl CompileJob {
+    fn label(&self) -> String {
+        Path::new(&self.source)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.source.clone())
+    }
 }
@@ -0,0 +1,295 @@
+use crate::manifest::{
+    lints::{LintLevel, Lints},
+    profile::Profile,
+    sanitizer::Sanitizer,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Family {
+    Gcc,
+    Clang,
+    Tcc,
+}
+
+impl std::fmt::Display for Family {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Family::Gcc => write!(f, "gcc"),
+            Family::Clang => write!(f, "clang"),
+            Family::Tcc => write!(f, "tcc"),
+        }
+    }
+}
+
+/// Compiler family and version, detected by running the compiler.
+///
+/// Profiles describe abstract settings (optimization, debug info, LTO,
+/// warnings-as-errors, sanitizers); this type maps them to the command-line
+/// flags of each family and rejects the ones a family does not support.
+#[derive(Clone)]
+pub struct CompilerFamily {
+    family: Family,
+    version: String,
+}
+
+impl CompilerFamily {
+    /// Runs `<compiler> -v`, which prints a `<family> version <x.y.z>` line for
+    /// gcc, clang and tcc alike (`--version` banners vary between distributions).
+    pub fn detect(compiler: &[String]) -> Result<Self, String> {
+        let output = std::process::Command::new(&compiler[0])
+            .args(&compiler[1..])
+            .arg("-v")
+            .output()
+            .map_err(|e| format!("failed to run compiler `{}`: {}", compiler.join(" "), e))?;
+
+        let mut banner = String::from_utf8_lossy(&output.stdout).to_string();
+        banner.push_str(&String::from_utf8_lossy(&output.stderr));
+
+        Self::parse(&banner).ok_or(format!(
+            "unrecognized compiler `{}`; supported compilers are gcc, clang and tcc",
+            compiler.join(" ")
+        ))
+    }
+
+    fn parse(banner: &str) -> Option<Self> {
+        banner.lines().find_map(|line| {
+            let (family, version) = if let Some(rest) = line.strip_prefix("gcc version ") {
+                (Family::Gcc, rest)
+            } else if let Some(rest) = line.strip_prefix("tcc version ") {
+                (Family::Tcc, rest)
+            } else if let Some((_, rest)) = line.split_once("clang version ") {
+                // Also matches vendor builds, e.g. "Apple clang version 15.0.0"
+                (Family::Clang, rest)
+            } else {
+                return None;
+            };
+
+            Some(Self {
+                family,
+                version: version.split_whitespace().next()?.to_string(),
+            })
+        })
+    }
+
+    /// Identifies the compiler in build fingerprints, so upgrading it triggers a rebuild.
+    pub fn fingerprint(&self) -> String {
+        format!("{} {}", self.family, self.version)
+    }
+
+    fn unsupported(&self, profile: &Profile, setting: &str) -> String {
+        format!(
+            "profile `{}` uses {}, which {} {} does not support",
+            profile.name(),
+            setting,
+            self.family,
+            self.version
+        )
+    }
+
+    /// Flags for the profile's abstract settings, added to every compile command.
+    pub fn compile_flags(&self, profile: &Profile) -> Result<Vec<String>, String> {
+        let mut flags = vec![];
+
+        match self.family {
+            // tcc has no optimizer; it accepts and ignores `-O` flags, so don't pretend.
+            Family::Tcc => {}
+            Family::Gcc | Family::Clang => flags.push(format!("-O{}", profile.opt_level())),
+        }
+
+        match (self.family, profile.debug_level()) {
+            (_, 0) => {}
+            (Family::Tcc, _) | (_, 2) => flags.push("-g".to_string()),
+            (_, level) => flags.push(format!("-g{}", level)),
+        }
+
+        if profile.lto() {
+            if self.family == Family::Tcc {
+                return Err(self.unsupported(profile, "link-time optimization"));
+            }
+            flags.push("-flto".to_string());
+        }
+
+        if profile.warnings_as_errors() {
+            flags.push("-Werror".to_string());
+        }
+
+        flags.extend(self.sanitizer_flags(profile)?);
+        flags.extend(self.coverage_flags(profile)?);
+
+        Ok(flags)
+    }
+
+    /// Flags for the profile's abstract settings, added to every link command.
+    pub fn link_flags(&self, profile: &Profile) -> Result<Vec<String>, String> {
+        let mut flags = vec![];
+
+        if profile.lto() {
+            flags.push(format!("-O{}", profile.opt_level()));
+            flags.push("-flto".to_string());
+        }
+
+        flags.extend(self.sanitizer_flags(profile)?);
+        flags.extend(self.coverage_flags(profile)?);
+
+        Ok(flags)
+    }
+
+    /// `--coverage`, understood by gcc and clang alike: both write gcov's
+    /// `.gcno` notes next to each object and `.gcda` counts when tests run.
+    fn coverage_flags(&self, profile: &Profile) -> Result<Vec<String>, String> {
+        match (profile.coverage(), self.family) {
+            (false, _) => Ok(vec![]),
+            (true, Family::Tcc) => Err(self.unsupported(profile, "coverage instrumentation")),
+            (true, Family::Gcc | Family::Clang) => Ok(vec!["--coverage".to_string()]),
+        }
+    }
+
+    /// The gcov of `compiler`, which reads its coverage data: `gcov` for gcc
+    /// and `llvm-cov gcov` for clang, with the same prefix and version suffix
+    /// as the compiler (e.g. `gcc-13` uses `gcov-13`).
+    pub fn gcov(&self, compiler: &[String]) -> Result<Vec<String>, String> {
+        let (tool, default) = match self.family {
+            Family::Gcc => ("gcc", vec!["gcov".to_string()]),
+            Family::Clang => ("clang", vec!["llvm-cov".to_string(), "gcov".to_string()]),
+            Family::Tcc => {
+                return Err(format!(
+                    "{} {} cannot measure coverage",
+                    self.family, self.version
+                ));
+            }
+        };
+        let program = std::path::Path::new(&compiler[0]);
+        let Some(name) = program
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .filter(|name| name.contains(tool))
+        else {
+            return Ok(default);
+        };
+
+        let mut gcov = default;
+        gcov[0] = program
+            .with_file_name(name.replacen(tool, &gcov[0], 1))
+            .to_string_lossy()
+            .to_string();
+        Ok(gcov)
+    }
+
+    fn sanitizer_flags(&self, profile: &Profile) -> Result<Vec<String>, String> {
+        let sanitizers = profile.sanitizers();
+        if sanitizers.is_empty() {
+            return Ok(vec![]);
+        }
+
+        for sanitizer in sanitizers {
+            let supported = !matches!(
+                (self.family, sanitizer),
+                (Family::Tcc, _) | (Family::Gcc, Sanitizer::Memory)
+            );
+            if !supported {
+                return Err(self.unsupported(profile, &format!("the {} sanitizer", sanitizer)));
+            }
+        }
+
+        let has = |sanitizer| sanitizers.contains(&sanitizer);
+        if has(Sanitizer::Thread) && (has(Sanitizer::Address) || has(Sanitizer::Leak)) {
+            return Err(format!(
+                "profile `{}`: the thread sanitizer cannot be combined with the address or leak sanitizers",
+                profile.name()
+            ));
+        }
+        if has(Sanitizer::Memory)
+            && sanitizers.len() > 1
+            && !(sanitizers.len() == 2 && has(Sanitizer::Undefined))
+        {
+            return Err(format!(
+                "profile `{}`: the memory sanitizer can only be combined with the undefined sanitizer",
+                profile.name()
+            ));
+        }
+
+        Ok(vec![
+            format!(
+                "-fsanitize={}",
+                sanitizers
+                    .iter()
+                    .map(|sanitizer| sanitizer.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            "-fno-omit-frame-pointer".to_string(),
+        ])
+    }
+
+    /// Flags for a package's warning policy.
+    ///
+    /// `capped` packages (dependencies built with `--verbose`) report their
+    /// warnings but never fail on them, even with a `warnings-as-errors` profile.
+    pub fn warning_flags(&self, lints: &Lints, capped: bool) -> Result<Vec<String>, String> {
+        let mut flags = vec![];
+        let mut denied_groups = vec![];
+        // Named warnings that stay warnings (or off) even in a denied group.
+        let mut relaxed = vec![];
+
+        for (name, level) in lints.levels() {
+            // `-Wformat=2` is turned into an error as `-Werror=format`.
+            let base = name.split('=').next().unwrap_or(name);
+            let group = Lints::GROUPS.contains(&name.as_str());
+            match (level, capped) {
+                (LintLevel::Allow, _) => {
+                    flags.push(format!("-Wno-{}", base));
+                    relaxed.push(base);
+                }
+                (LintLevel::Warn, _) | (LintLevel::Deny, true) => {
+                    flags.push(format!("-W{}", name));
+                    if !group {
+                        relaxed.push(base);
+                    }
+                }
+                (LintLevel::Deny, false) if group => {
+                    flags.push(format!("-W{}", name));
+                    denied_groups.push(name);
+                }
+                (LintLevel::Deny, false) => {
+                    if self.family == Family::Tcc {
+                        return Err(format!(
+                            "lint `{}` is denied, but {} {} cannot turn a single warning into an error",
+                            name, self.family, self.version
+                        ));
+                    }
+                    flags.push(format!("-W{}", name));
+                    flags.push(format!("-Werror={}", base));
+                }
+            }
+        }
+
+        if !denied_groups.is_empty() {
+            match self.family {
+                // tcc cannot tell warnings apart: denying a group denies them all.
+                Family::Tcc => flags.push("-Werror".to_string()),
+                Family::Gcc | Family::Clang => {
+                    flags.extend(
+                        denied_groups
+                            .iter()
+                            .map(|group| format!("-Werror={}", group)),
+                    );
+                    flags.extend(relaxed.iter().map(|name| format!("-Wno-error={}", name)));
+                }
+            }
+        }
+        if capped {
+            flags.push("-Wno-error".to_string());
+        }
+
+        Ok(flags)
+    }
+
+    /// Flags asking the compiler to write a make-style depfile next to the object.
+    pub fn depfile_flags(&self, depfile: &str) -> Vec<String> {
+        let generate = match self.family {
+            Family::Tcc => "-MD",
+            Family::Gcc | Family::Clang => "-MMD",
+        };
+        vec![generate.to_string(), "-MF".to_string(), depfile.to_string()]
+    }
+}
@@ -0,0 +1,93 @@
+use crate::manifest::{Manifest, features::Features};
+use std::path::{Path, PathBuf};
+
+/// `<name>_config.h`, generated for packages that set `config-header = true`:
+///
+/// ```c
+/// #define ZLIB_PKG_NAME "zlib"
+/// #define ZLIB_PKG_VERSION "1.3.1"
+/// #define ZLIB_PKG_EDITION "2026.1"
+/// #define LEVEL 6
+/// #define TAILOR_FEATURE_STD 1
+/// ```
+///
+/// It holds what would otherwise be the package's `-D` flags, so changing an
+/// option only recompiles the sources that include the header (through their
+/// depfiles) instead of every source of the package. Metadata macros are
+/// prefixed with the package name, as dependents may include several headers.
+pub struct ConfigHeader;
+
+impl ConfigHeader {
+    /// `<build_path>/gen/<name>`, the include directory of the header.
+    fn directory(build_path: &Path, manifest: &Manifest) -> PathBuf {
+        build_path.join("gen").join(manifest.name())
+    }
+
+    pub fn render(manifest: &Manifest) -> String {
+        let prefix = manifest
+            .name()
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c.to_ascii_uppercase(),
+                false => '_',
+            })
+            .collect::<String>();
+        let guard = format!("{}_CONFIG_H", prefix);
+
+        let mut lines = vec![
+            format!(
+                "/* Generated by tailor for {} v{}; do not edit. */",
+                manifest.name(),
+                manifest.version()
+            ),
+            format!("#ifndef {}", guard),
+            format!("#define {}", guard),
+            "".to_string(),
+            format!("#define {}_PKG_NAME \"{}\"", prefix, manifest.name()),
+            format!("#define {}_PKG_VERSION \"{}\"", prefix, manifest.version()),
+            format!("#define {}_PKG_EDITION \"{}\"", prefix, manifest.edition()),
+        ];
+        if !manifest.options().is_empty() {
+            let declared = manifest.options_schema().is_some();
+            lines.push("".to_string());
+            lines.extend(
+                manifest
+                    .options()
+                    .iter()
+                    .map(|option| option.to_define_line(declared)),
+            );
+        }
+        if !manifest.enabled_features().is_empty() {
+            lines.push("".to_string());
+            lines.extend(
+                manifest
+                    .enabled_features()
+                    .iter()
+                    .map(|feature| format!("#define {} 1", Features::variable(feature))),
+            );
+        }
+        lines.extend(["".to_string(), format!("#endif /* {} */", guard)]);
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Writes the header, leaving it untouched when its contents did not
+    /// change so that the sources including it are not recompiled.
+    ///
+    /// Returns the include directory of the header.
+    pub fn write(build_path: &Path, manifest: &Manifest) -> Result<PathBuf, String> {
+        let directory = Self::directory(build_path, manifest);
+        let path = directory.join(format!("{}_config.h", manifest.name()));
+        let content = Self::render(manifest);
+
+        if std::fs::read_to_string(&path).is_ok_and(|current| current == content) {
+            return Ok(directory);
+        }
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| format!("fail to create {}: {}", directory.display(), e))?;
+        std::fs::write(&path, content)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+
+        Ok(directory)
+    }
+}
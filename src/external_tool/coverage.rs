@@ -0,0 +1,412 @@
+use crate::external_tool::test_report::escape;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Execution counts of a source file, merged over every object it is part of.
+#[derive(Default)]
+struct FileCoverage {
+    /// Count of each executable line.
+    lines: BTreeMap<u32, u64>,
+    /// Times each branch of a line was taken, `None` when its line never ran.
+    branches: BTreeMap<(u32, u32), Option<u64>>,
+    /// First line and call count of each function.
+    functions: BTreeMap<String, (u32, u64)>,
+}
+
+impl FileCoverage {
+    fn lines_hit(&self) -> (usize, usize) {
+        let hit = self.lines.values().filter(|count| **count > 0).count();
+        (hit, self.lines.len())
+    }
+
+    fn branches_hit(&self) -> (usize, usize) {
+        let hit = self
+            .branches
+            .values()
+            .filter(|taken| taken.is_some_and(|taken| taken > 0))
+            .count();
+        (hit, self.branches.len())
+    }
+
+    fn functions_hit(&self) -> (usize, usize) {
+        let hit = self
+            .functions
+            .values()
+            .filter(|(_, count)| *count > 0)
+            .count();
+        (hit, self.functions.len())
+    }
+
+    fn line_branches(&self, line: u32) -> (usize, usize) {
+        let branches = self
+            .branches
+            .range((line, 0)..=(line, u32::MAX))
+            .map(|(_, taken)| taken)
+            .collect::<Vec<_>>();
+        let hit = branches
+            .iter()
+            .filter(|taken| taken.is_some_and(|taken| taken > 0))
+            .count();
+        (hit, branches.len())
+    }
+}
+
+/// Line, branch and function coverage of a `tailor test --coverage` run.
+///
+/// Objects compiled with `--coverage` have gcov notes (`.gcno`) next to them,
+/// and the tests write their counts (`.gcda`) there too. gcov (or `llvm-cov
+/// gcov`) turns both into an annotated `.gcov` listing per source file, whose
+/// text format the two tools share.
+pub struct Coverage {
+    files: BTreeMap<PathBuf, FileCoverage>,
+}
+
+impl Coverage {
+    /// Removes the counts of previous runs from `build_path`, so that the
+    /// report only covers the tests about to run.
+    pub fn clear(build_path: &Path) -> Result<(), String> {
+        for entry in walkdir::WalkDir::new(build_path.join("obj"))
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "gcda"))
+        {
+            std::fs::remove_file(entry.path())
+                .map_err(|e| format!("failed to remove {}: {}", entry.path().display(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Runs `gcov` on the instrumented `objects` of `build_path`, keeping the
+    /// source files for which `measured` holds.
+    ///
+    /// Other notes under `build_path` are left out: those of packages that
+    /// were not built this time, or of sources since removed.
+    pub fn collect(
+        gcov: &[String],
+        build_path: &Path,
+        objects: &[PathBuf],
+        measured: impl Fn(&Path) -> bool,
+    ) -> Result<Self, String> {
+        let mut coverage = Self {
+            files: BTreeMap::new(),
+        };
+        // gcov writes its listings to the current directory; one object at a
+        // time, so that headers included by several objects don't overwrite each other.
+        let scratch = build_path.join("coverage").join("gcov");
+
+        let mut notes = objects
+            .iter()
+            .map(|object| object.with_extension("gcno"))
+            .filter(|note| note.exists())
+            .collect::<Vec<_>>();
+        notes.sort();
+        notes.dedup();
+
+        for note in notes {
+            if scratch.exists() {
+                std::fs::remove_dir_all(&scratch)
+                    .map_err(|e| format!("failed to remove {}: {}", scratch.display(), e))?;
+            }
+            std::fs::create_dir_all(&scratch)
+                .map_err(|e| format!("fail to create {}: {}", scratch.display(), e))?;
+
+            let object = note.with_extension("o");
+            let output = std::process::Command::new(&gcov[0])
+                .args(&gcov[1..])
+                .args(["-b", "-c", "-p"])
+                .arg(&object)
+                .current_dir(&scratch)
+                .output()
+                .map_err(|e| format!("failed to run `{}`: {}", gcov.join(" "), e))?;
+            if !output.status.success() {
+                return Err(format!(
+                    "`{}` failed on {}:\n{}",
+                    gcov.join(" "),
+                    object.display(),
+                    String::from_utf8_lossy(&output.stderr).trim_end()
+                ));
+            }
+
+            let listings = std::fs::read_dir(&scratch)
+                .map_err(|e| format!("failed to read {}: {}", scratch.display(), e))?;
+            for listing in listings.filter_map(|entry| entry.ok()) {
+                let content = std::fs::read_to_string(listing.path())
+                    .map_err(|e| format!("failed to read {}: {}", listing.path().display(), e))?;
+                coverage.add_listing(&content, &measured);
+            }
+        }
+        if scratch.exists() {
+            std::fs::remove_dir_all(&scratch)
+                .map_err(|e| format!("failed to remove {}: {}", scratch.display(), e))?;
+        }
+
+        Ok(coverage)
+    }
+
+    /// Adds the counts of a `.gcov` listing, made of `<count>:<line>:<source>`
+    /// lines (`-` when not executable, `#####` when never executed), preceded
+    /// by `function <name> called <n> ...` and followed by
+    /// `branch <i> taken <n>` or `branch <i> never executed` with `-b -c`.
+    fn add_listing(&mut self, content: &str, measured: &impl Fn(&Path) -> bool) {
+        let mut file = None;
+        let mut line_number = 0;
+        let mut branch = 0;
+        let mut function: Option<(String, u64)> = None;
+
+        for line in content.lines() {
+            if let Some(rest) = line.strip_prefix("function ") {
+                let mut words = rest.split_whitespace();
+                let name = words.next().unwrap_or_default().to_string();
+                let called = words
+                    .skip_while(|word| *word != "called")
+                    .nth(1)
+                    .and_then(|count| count.parse::<u64>().ok())
+                    .unwrap_or(0);
+                function = Some((name, called));
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("branch ") {
+                let taken = rest
+                    .split_once(" taken ")
+                    .and_then(|(_, count)| count.split_whitespace().next()?.parse::<u64>().ok());
+                if let Some(file) = file.as_mut().and_then(|path| self.files.get_mut(path)) {
+                    let entry = file.branches.entry((line_number, branch)).or_insert(None);
+                    *entry = match (*entry, taken) {
+                        (Some(a), Some(b)) => Some(a + b),
+                        (a, b) => a.or(b),
+                    };
+                }
+                branch += 1;
+                continue;
+            }
+
+            let mut fields = line.splitn(3, ':');
+            let (Some(count), Some(number), Some(source)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(number) = number.trim().parse::<u32>() else {
+                continue;
+            };
+            if number == 0 {
+                if let Some(path) = source.strip_prefix("Source:") {
+                    let path = PathBuf::from(path);
+                    file = match measured(&path) {
+                        true => {
+                            self.files.entry(path.clone()).or_default();
+                            Some(path)
+                        }
+                        false => None,
+                    };
+                }
+                continue;
+            }
+            line_number = number;
+            branch = 0;
+
+            let Some(file) = file.as_ref().and_then(|path| self.files.get_mut(path)) else {
+                continue;
+            };
+            if let Some((name, called)) = function.take() {
+                let entry = file.functions.entry(name).or_insert((number, 0));
+                entry.1 += called;
+            }
+            let count = match count.trim() {
+                "-" => continue,
+                "#####" | "=====" => 0,
+                count => count.trim_end_matches('*').parse::<u64>().unwrap_or(0),
+            };
+            *file.lines.entry(number).or_insert(0) += count;
+        }
+    }
+
+    fn percentage((hit, total): (usize, usize)) -> String {
+        match total {
+            0 => "-".to_string(),
+            total => format!("{:.2}%", hit as f64 * 100.0 / total as f64),
+        }
+    }
+
+    fn rate((hit, total): (usize, usize)) -> f64 {
+        match total {
+            0 => 1.0,
+            total => hit as f64 / total as f64,
+        }
+    }
+
+    fn total(&self, hit: fn(&FileCoverage) -> (usize, usize)) -> (usize, usize) {
+        self.files
+            .values()
+            .map(hit)
+            .fold((0, 0), |(a, b), (c, d)| (a + c, b + d))
+    }
+
+    /// `path` relative to `root` when it is under it.
+    fn display(path: &Path, root: &Path) -> String {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// A table of the lines, functions and branches hit in each file, with
+    /// paths relative to `root`.
+    pub fn summary(&self, root: &Path) -> Vec<String> {
+        let cell = |(hit, total): (usize, usize)| {
+            format!(
+                "{:>11} {:>7}",
+                format!("{}/{}", hit, total),
+                Self::percentage((hit, total))
+            )
+        };
+        let mut rows = self
+            .files
+            .iter()
+            .map(|(path, file)| {
+                (
+                    Self::display(path, root),
+                    cell(file.lines_hit()),
+                    cell(file.functions_hit()),
+                    cell(file.branches_hit()),
+                )
+            })
+            .collect::<Vec<_>>();
+        rows.push((
+            "TOTAL".to_string(),
+            cell(self.total(FileCoverage::lines_hit)),
+            cell(self.total(FileCoverage::functions_hit)),
+            cell(self.total(FileCoverage::branches_hit)),
+        ));
+
+        let width = rows
+            .iter()
+            .map(|(name, ..)| name.len())
+            .max()
+            .unwrap_or(0)
+            .max("File".len());
+        let mut lines = vec![format!(
+            "{:<width$}  {:>19}  {:>19}  {:>19}",
+            "File", "Lines", "Functions", "Branches"
+        )];
+        lines.extend(rows.into_iter().map(|(name, lines, functions, branches)| {
+            format!("{:<width$}  {}  {}  {}", name, lines, functions, branches)
+        }));
+        lines
+    }
+
+    /// The lcov tracefile (`.info`), read by `genhtml` and most CI services.
+    pub fn lcov(&self) -> String {
+        let mut lines = vec![];
+        for (path, file) in &self.files {
+            lines.push("TN:".to_string());
+            lines.push(format!("SF:{}", path.display()));
+
+            let mut functions = file.functions.iter().collect::<Vec<_>>();
+            functions.sort_by_key(|(name, (line, _))| (*line, name.as_str()));
+            for (name, (line, _)) in &functions {
+                lines.push(format!("FN:{},{}", line, name));
+            }
+            for (name, (_, count)) in &functions {
+                lines.push(format!("FNDA:{},{}", count, name));
+            }
+            let (hit, total) = file.functions_hit();
+            lines.extend([format!("FNF:{}", total), format!("FNH:{}", hit)]);
+
+            for ((line, branch), taken) in &file.branches {
+                let taken = taken.map_or("-".to_string(), |taken| taken.to_string());
+                lines.push(format!("BRDA:{},0,{},{}", line, branch, taken));
+            }
+            let (hit, total) = file.branches_hit();
+            lines.extend([format!("BRF:{}", total), format!("BRH:{}", hit)]);
+
+            for (line, count) in &file.lines {
+                lines.push(format!("DA:{},{}", line, count));
+            }
+            let (hit, total) = file.lines_hit();
+            lines.extend([format!("LF:{}", total), format!("LH:{}", hit)]);
+            lines.push("end_of_record".to_string());
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    /// A Cobertura XML report, with a class per file of the package `name`
+    /// and paths relative to `root`.
+    pub fn cobertura(&self, root: &Path, name: &str) -> String {
+        let lines_hit = self.total(FileCoverage::lines_hit);
+        let branches_hit = self.total(FileCoverage::branches_hit);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+
+        let mut lines = vec![
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>".to_string(),
+            "<!DOCTYPE coverage SYSTEM \"http://cobertura.sourceforge.net/xml/coverage-04.dtd\">"
+                .to_string(),
+            format!(
+                "<coverage line-rate=\"{:.4}\" branch-rate=\"{:.4}\" lines-covered=\"{}\" lines-valid=\"{}\" branches-covered=\"{}\" branches-valid=\"{}\" complexity=\"0\" version=\"tailor {}\" timestamp=\"{}\">",
+                Self::rate(lines_hit),
+                Self::rate(branches_hit),
+                lines_hit.0,
+                lines_hit.1,
+                branches_hit.0,
+                branches_hit.1,
+                env!("CARGO_PKG_VERSION"),
+                timestamp
+            ),
+            "  <sources>".to_string(),
+            format!("    <source>{}</source>", escape(&root.to_string_lossy())),
+            "  </sources>".to_string(),
+            "  <packages>".to_string(),
+            format!(
+                "    <package name=\"{}\" line-rate=\"{:.4}\" branch-rate=\"{:.4}\" complexity=\"0\">",
+                escape(name),
+                Self::rate(lines_hit),
+                Self::rate(branches_hit)
+            ),
+            "      <classes>".to_string(),
+        ];
+        for (path, file) in &self.files {
+            let filename = Self::display(path, root);
+            lines.push(format!(
+                "        <class name=\"{}\" filename=\"{}\" line-rate=\"{:.4}\" branch-rate=\"{:.4}\" complexity=\"0\">",
+                escape(&filename),
+                escape(&filename),
+                Self::rate(file.lines_hit()),
+                Self::rate(file.branches_hit())
+            ));
+            lines.push("          <methods/>".to_string());
+            lines.push("          <lines>".to_string());
+            for (line, count) in &file.lines {
+                let branches = match file.line_branches(*line) {
+                    (_, 0) => " branch=\"false\"".to_string(),
+                    (hit, total) => format!(
+                        " branch=\"true\" condition-coverage=\"{}% ({}/{})\"",
+                        hit * 100 / total,
+                        hit,
+                        total
+                    ),
+                };
+                lines.push(format!(
+                    "            <line number=\"{}\" hits=\"{}\"{}/>",
+                    line, count, branches
+                ));
+            }
+            lines.push("          </lines>".to_string());
+            lines.push("        </class>".to_string());
+        }
+        lines.extend([
+            "      </classes>".to_string(),
+            "    </package>".to_string(),
+            "  </packages>".to_string(),
+            "</coverage>".to_string(),
+        ]);
+
+        lines.join("\n") + "\n"
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Make-style dependency file emitted by the compiler with `-MMD -MF`.
+pub struct DepFile {
+    prerequisites: Vec<PathBuf>,
+}
+
+impl DepFile {
+    pub fn new(prerequisites: Vec<PathBuf>) -> Self {
+        Self { prerequisites }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read depfile {}: {}", path.display(), err))?;
+
+        Ok(Self::parse(&content))
+    }
+
+    fn parse(content: &str) -> Self {
+        // Only the first rule matters: `-MMD` never emits phony targets unless `-MP` is given.
+        let rule = content.replace("\\\n", " ").replace("\\\r\n", " ");
+        let rule = rule.lines().next().unwrap_or_default();
+        let prerequisites = rule
+            .split_once(": ")
+            .map(|(_, prerequisites)| prerequisites)
+            .unwrap_or_default();
+
+        let mut paths = vec![];
+        let mut current = String::new();
+        let mut chars = prerequisites.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if matches!(chars.peek(), Some(' ' | '#')) => {
+                    current.extend(chars.next());
+                }
+                '$' if chars.peek() == Some(&'$') => {
+                    current.push('$');
+                    chars.next();
+                }
+                c if c.is_whitespace() => {
+                    if !current.is_empty() {
+                        paths.push(PathBuf::from(std::mem::take(&mut current)));
+                    }
+                }
+                c => current.push(c),
+            }
+        }
+
+        if !current.is_empty() {
+            paths.push(PathBuf::from(current));
+        }
+
+        Self::new(paths)
+    }
+
+    /// Returns `true` when any prerequisite is missing or was modified after `timestamp`.
+    pub fn is_newer_than(&self, timestamp: SystemTime) -> bool {
+        self.prerequisites.iter().any(|prerequisite| {
+            std::fs::metadata(prerequisite)
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| modified > timestamp)
+                .unwrap_or(true)
+        })
+    }
+}
@@ -0,0 +1,125 @@
+use serde::Serialize;
+
+/// How build progress and compiler diagnostics are reported, from `--message-format`.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum MessageFormat {
+    /// Progress bar, then a summary of the diagnostics grouped by package and file.
+    #[default]
+    Human,
+    /// One JSON object per line on stdout for every diagnostic and artifact;
+    /// human-readable status goes to stderr.
+    Json,
+}
+
+impl TryFrom<&str> for MessageFormat {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A diagnostic parsed from gcc/clang output (`file:line:column: severity: message`).
+#[derive(Clone, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub column: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Extracts the diagnostics from a compiler's captured output.
+    ///
+    /// Context lines (`In function ...`, source excerpts, carets) are skipped.
+    pub fn parse(output: &str) -> Vec<Self> {
+        output.lines().filter_map(Self::parse_line).collect()
+    }
+
+    /// The lines of a compiler's captured output that are neither diagnostics
+    /// nor their context, e.g. `cc1: error: ...` or `gcc: error: ...`.
+    pub fn unparsed(output: &str) -> Vec<&str> {
+        output
+            .lines()
+            .filter(|line| Self::parse_line(line).is_none())
+            .filter(|line| {
+                // Excerpts and carets are indented; headers such as `In function
+                // 'main':` or `In file included from x.h:1,` end with `:` or `,`,
+                // and clang ends with `N warnings generated.`
+                !(line.trim().is_empty()
+                    || line.starts_with(char::is_whitespace)
+                    || line.ends_with(':')
+                    || line.ends_with(',')
+                    || line.ends_with(" generated."))
+            })
+            .collect()
+    }
+
+    fn parse_line(line: &str) -> Option<Self> {
+        let (index, severity, marker) = [
+            (Severity::Error, ": fatal error: "),
+            (Severity::Error, ": error: "),
+            (Severity::Warning, ": warning: "),
+            (Severity::Note, ": note: "),
+        ]
+        .into_iter()
+        .filter_map(|(severity, marker)| line.find(marker).map(|index| (index, severity, marker)))
+        .min_by_key(|(index, _, _)| *index)?;
+
+        let location = &line[..index];
+        let message = line[index + marker.len()..].to_string();
+
+        // `file:line:column`, or `file:line` for compilers that omit the column
+        let mut parts = location.rsplitn(3, ':').collect::<Vec<_>>();
+        parts.reverse();
+        let (file, line, column) = match parts.as_slice() {
+            [file, line, column] if column.parse::<usize>().is_ok() => {
+                (file.to_string(), line.parse().ok()?, column.parse().ok())
+            }
+            [.., file, line] => (file.to_string(), line.parse().ok()?, None),
+            _ => return None,
+        };
+        if file.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            file,
+            line,
+            column,
+            severity,
+            message,
+        })
+    }
+
+    /// `line:column`, or just `line`.
+    pub fn position(&self) -> String {
+        match self.column {
+            Some(column) => format!("{}:{}", self.line, column),
+            None => self.line.to_string(),
+        }
+    }
+}
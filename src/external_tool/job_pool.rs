@@ -0,0 +1,81 @@
+use std::{
+    collections::VecDeque,
+    process::{Command, Output},
+    sync::{Mutex, mpsc},
+};
+
+pub enum JobEvent {
+    Started(usize),
+    Finished(usize, Result<Output, String>),
+}
+
+/// Runs independent commands on a fixed number of worker threads.
+///
+/// Outputs are captured and handed back to the caller as whole units, so
+/// the caller decides when to print them and parallel jobs never interleave.
+pub struct JobPool {
+    workers: usize,
+}
+
+impl JobPool {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+        }
+    }
+
+    pub fn default_workers() -> usize {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    }
+
+    /// Runs every command, reporting progress through `on_event` on the calling thread.
+    ///
+    /// Returning `false` from `on_event` stops scheduling new jobs; jobs already
+    /// running are waited for.
+    pub fn run<F>(&self, commands: Vec<Command>, mut on_event: F)
+    where
+        F: FnMut(JobEvent) -> bool,
+    {
+        let queue = Mutex::new(commands.into_iter().enumerate().collect::<VecDeque<_>>());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.workers {
+                let tx = tx.clone();
+                let queue = &queue;
+
+                scope.spawn(move || {
+                    loop {
+                        let Some((id, mut command)) =
+                            queue.lock().ok().and_then(|mut queue| queue.pop_front())
+                        else {
+                            break;
+                        };
+
+                        if tx.send(JobEvent::Started(id)).is_err() {
+                            break;
+                        }
+
+                        let output = command
+                            .output()
+                            .map_err(|e| format!("failed to execute command: {}", e));
+                        if tx.send(JobEvent::Finished(id, output)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(tx);
+
+            for event in rx {
+                if !on_event(event)
+                    && let Ok(mut queue) = queue.lock()
+                {
+                    queue.clear();
+                }
+            }
+        });
+    }
+}
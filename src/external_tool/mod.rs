@@ -1,5 +1,16 @@
+pub mod build_script;
 pub mod checksum;
 pub mod compile_commands;
 pub mod compiler;
+pub mod compiler_family;
+pub mod config_header;
+pub mod coverage;
+pub mod depfile;
+pub mod diagnostic;
 pub mod git;
+pub mod job_pool;
+pub mod pkg_config;
 pub mod registry;
+pub mod test_cases;
+pub mod test_report;
+pub mod test_runner;
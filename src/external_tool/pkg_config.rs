@@ -0,0 +1,54 @@
+use crate::manifest::system_dependency::SystemDependency;
+
+/// Compiler and linker flags of a system library, as reported by `pkg-config`.
+#[derive(Clone)]
+pub struct SystemLibrary {
+    pub cflags: Vec<String>,
+    pub libs: Vec<String>,
+}
+
+pub struct PkgConfig;
+
+impl PkgConfig {
+    pub fn probe(dependency: &SystemDependency) -> Result<SystemLibrary, String> {
+        let output = std::process::Command::new("pkg-config")
+            .arg("--exists")
+            .arg("--print-errors")
+            .arg(dependency.module_spec())
+            .output()
+            .map_err(|e| format!("failed to execute pkg-config: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "system dependency `{}` not found: {}",
+                dependency.module_spec(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(SystemLibrary {
+            cflags: Self::query(dependency.name(), "--cflags")?,
+            libs: Self::query(dependency.name(), "--libs")?,
+        })
+    }
+
+    fn query(name: &str, flag: &str) -> Result<Vec<String>, String> {
+        let output = std::process::Command::new("pkg-config")
+            .arg(flag)
+            .arg(name)
+            .output()
+            .map_err(|e| format!("failed to execute pkg-config: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "pkg-config {} {} failed: {}",
+                flag,
+                name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(String::from)
+            .collect())
+    }
+}
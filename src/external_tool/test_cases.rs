@@ -0,0 +1,206 @@
+use crate::external_tool::test_runner::{Outcome, TestResult};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// `tailor_test.h`, the harness whose cases `tailor test` reports, on the
+/// include path of tests and examples.
+pub struct TestHeader;
+
+impl TestHeader {
+    const CONTENT: &str = include_str!("../../template/tailor_test.h");
+
+    /// Writes the header to `<build_path>/gen/.tailor` unless it is already
+    /// there, and returns that include directory.
+    pub fn write(build_path: &Path) -> Result<PathBuf, String> {
+        let directory = build_path.join("gen").join(".tailor");
+        let path = directory.join("tailor_test.h");
+
+        if std::fs::read_to_string(&path).is_ok_and(|current| current == Self::CONTENT) {
+            return Ok(directory);
+        }
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| format!("fail to create {}: {}", directory.display(), e))?;
+        std::fs::write(&path, Self::CONTENT)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+
+        Ok(directory)
+    }
+}
+
+pub enum CaseStatus {
+    Passed,
+    /// Failed, with the failure message.
+    Failed(String),
+    /// Skipped, with the reason when given.
+    Skipped(String),
+}
+
+/// A case of a test executable, reported on its stdout.
+pub struct TestCase {
+    pub name: String,
+    pub status: CaseStatus,
+    pub duration: Option<Duration>,
+}
+
+impl TestCase {
+    /// The cases of `result`, as reported by `tailor_test.h`, Unity, cmocka
+    /// or greatest on the test's stdout.
+    ///
+    /// A test reporting no case is a case of its own; one that failed without
+    /// a failing case (a crash, a timeout) gets a failing case for the executable.
+    pub fn from_result(result: &TestResult, timeout: Duration) -> Vec<Self> {
+        let mut cases = Self::parse(&result.stdout);
+
+        let status = match &result.outcome {
+            Outcome::Passed if cases.is_empty() => CaseStatus::Passed,
+            Outcome::Passed => return cases,
+            _ if cases
+                .iter()
+                .any(|case| matches!(case.status, CaseStatus::Failed(_))) =>
+            {
+                return cases;
+            }
+            Outcome::Failed(reason) => CaseStatus::Failed(reason.clone()),
+            Outcome::TimedOut => {
+                CaseStatus::Failed(format!("timed out after {}s", timeout.as_secs()))
+            }
+        };
+        cases.push(Self {
+            name: result.name.clone(),
+            status,
+            duration: Some(result.duration),
+        });
+        cases
+    }
+
+    fn parse(stdout: &str) -> Vec<Self> {
+        let mut cases = vec![];
+        // cmocka reports a case's errors between its RUN and FAILED lines.
+        let mut running: Option<(String, Vec<String>)> = None;
+        // greatest's case lines are too loose to be trusted without its summary.
+        let greatest = stdout
+            .lines()
+            .any(|line| Self::greatest_summary(line).is_some());
+
+        for line in stdout.lines() {
+            if let Some(case) = Self::parse_tailor(line)
+                .or_else(|| Self::parse_unity(line))
+                .or_else(|| Self::parse_greatest(line).filter(|_| greatest))
+            {
+                cases.push(case);
+                continue;
+            }
+
+            let Some((tag, rest)) = line.strip_prefix('[').and_then(|line| line.split_once(']'))
+            else {
+                continue;
+            };
+            let rest = rest.trim();
+            match (tag.trim(), &mut running) {
+                ("RUN", _) => running = Some((rest.to_string(), vec![])),
+                ("ERROR" | "LINE", Some((_, messages))) => {
+                    messages.push(rest.trim_start_matches("---").trim().to_string())
+                }
+                (status @ ("OK" | "FAILED" | "SKIPPED"), Some((name, messages)))
+                    if name == rest =>
+                {
+                    let message = messages.join("\n");
+                    cases.push(Self {
+                        name: name.clone(),
+                        status: match status {
+                            "OK" => CaseStatus::Passed,
+                            "FAILED" => CaseStatus::Failed(message),
+                            _ => CaseStatus::Skipped(message),
+                        },
+                        duration: None,
+                    });
+                    running = None;
+                }
+                _ => {}
+            }
+        }
+
+        cases
+    }
+
+    /// `tailor-test: <ok|failed|skipped> <name> <seconds> [<message>]`, from `tailor_test.h`.
+    fn parse_tailor(line: &str) -> Option<Self> {
+        let mut fields = line.strip_prefix("tailor-test: ")?.splitn(4, ' ');
+        let status = fields.next()?;
+        let name = fields.next()?.to_string();
+        let seconds = fields.next()?.parse::<f64>().ok()?;
+        let message = fields.next().unwrap_or("").trim().to_string();
+
+        Some(Self {
+            name,
+            status: match status {
+                "ok" => CaseStatus::Passed,
+                "failed" => CaseStatus::Failed(message),
+                "skipped" => CaseStatus::Skipped(message),
+                _ => return None,
+            },
+            duration: Duration::try_from_secs_f64(seconds).ok(),
+        })
+    }
+
+    /// `<file>:<line>:<name>:<PASS|FAIL|IGNORE>[: <message>]`, from Unity.
+    fn parse_unity(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(5, ':');
+        let file = fields.next()?;
+        let line_number = fields.next()?.parse::<u32>().ok()?;
+        let name = fields.next()?.to_string();
+        let status = fields.next()?;
+        let message = fields.next().unwrap_or("").trim();
+        if file.is_empty() || name.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            name,
+            status: match status {
+                "PASS" => CaseStatus::Passed,
+                "FAIL" => CaseStatus::Failed(format!("{}:{}: {}", file, line_number, message)),
+                "IGNORE" => CaseStatus::Skipped(message.to_string()),
+                _ => return None,
+            },
+            duration: None,
+        })
+    }
+
+    /// `Pass: <n>, fail: <n>, skip: <n>.`, the counts ending greatest's report.
+    fn greatest_summary(line: &str) -> Option<[u32; 3]> {
+        let (passed, rest) = line.strip_prefix("Pass: ")?.split_once(", fail: ")?;
+        let (failed, skipped) = rest.split_once(", skip: ")?;
+        let skipped = skipped.trim_end().strip_suffix('.')?;
+
+        Some([
+            passed.parse().ok()?,
+            failed.parse().ok()?,
+            skipped.parse().ok()?,
+        ])
+    }
+
+    /// `<PASS|FAIL|SKIP> <name>: <message>`, from greatest in verbose mode.
+    fn parse_greatest(line: &str) -> Option<Self> {
+        let (status, rest) = line.split_once(' ')?;
+        let (name, message) = rest.split_once(':').unwrap_or((rest, ""));
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+        let message = message.trim().to_string();
+
+        Some(Self {
+            name: name.to_string(),
+            status: match status {
+                "PASS" => CaseStatus::Passed,
+                "FAIL" => CaseStatus::Failed(message),
+                "SKIP" => CaseStatus::Skipped(message),
+                _ => return None,
+            },
+            duration: None,
+        })
+    }
+}
@@ -0,0 +1,215 @@
+use crate::external_tool::{
+    test_cases::{CaseStatus, TestCase},
+    test_runner::TestResult,
+};
+use std::time::Duration;
+
+/// Format of the test results, from `tailor test --format`.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum ReportFormat {
+    /// A line per test and a summary only.
+    #[default]
+    Human,
+    /// JUnit XML: a `<testsuite>` per test executable, a `<testcase>` per case.
+    Junit,
+    /// TAP version 13: a test point per case, with YAML diagnostics.
+    Tap,
+}
+
+impl TryFrom<&str> for ReportFormat {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "human" => Ok(ReportFormat::Human),
+            "junit" => Ok(ReportFormat::Junit),
+            "tap" => Ok(ReportFormat::Tap),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The results of `tailor test` as JUnit XML or TAP.
+pub struct TestReport<'a> {
+    name: String,
+    results: &'a [TestResult],
+    timeout: Duration,
+}
+
+impl<'a> TestReport<'a> {
+    /// A report named `name`, the package or workspace, for `results`.
+    pub fn new(name: &str, results: &'a [TestResult], timeout: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            results,
+            timeout,
+        }
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Human => "".to_string(),
+            ReportFormat::Junit => self.junit(),
+            ReportFormat::Tap => self.tap(),
+        }
+    }
+
+    fn junit(&self) -> String {
+        let suites = self
+            .results
+            .iter()
+            .map(|result| (result, TestCase::from_result(result, self.timeout)))
+            .collect::<Vec<_>>();
+        let count = |cases: &[TestCase], matches: fn(&CaseStatus) -> bool| {
+            cases.iter().filter(|case| matches(&case.status)).count()
+        };
+        let failed = |status: &CaseStatus| matches!(status, CaseStatus::Failed(_));
+        let skipped = |status: &CaseStatus| matches!(status, CaseStatus::Skipped(_));
+
+        let mut lines = vec![
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>".to_string(),
+            format!(
+                "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+                escape(&self.name),
+                suites.iter().map(|(_, cases)| cases.len()).sum::<usize>(),
+                suites
+                    .iter()
+                    .map(|(_, cases)| count(cases, failed))
+                    .sum::<usize>(),
+                suites
+                    .iter()
+                    .map(|(_, cases)| count(cases, skipped))
+                    .sum::<usize>(),
+                self.results
+                    .iter()
+                    .map(|result| result.duration.as_secs_f64())
+                    .sum::<f64>()
+            ),
+        ];
+        for (result, cases) in &suites {
+            lines.push(format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+                escape(&result.name),
+                cases.len(),
+                count(cases, failed),
+                count(cases, skipped),
+                result.duration.as_secs_f64()
+            ));
+            for case in cases {
+                let time = case
+                    .duration
+                    .map(|duration| format!(" time=\"{:.3}\"", duration.as_secs_f64()))
+                    .unwrap_or_default();
+                let open = format!(
+                    "    <testcase name=\"{}\" classname=\"{}\"{}",
+                    escape(&case.name),
+                    escape(&result.name),
+                    time
+                );
+                match &case.status {
+                    CaseStatus::Passed => lines.push(format!("{}/>", open)),
+                    CaseStatus::Failed(message) => lines.extend([
+                        format!("{}>", open),
+                        format!(
+                            "      <failure message=\"{}\">{}</failure>",
+                            escape(message.lines().next().unwrap_or("")),
+                            escape(message)
+                        ),
+                        "    </testcase>".to_string(),
+                    ]),
+                    CaseStatus::Skipped(reason) => lines.extend([
+                        format!("{}>", open),
+                        format!("      <skipped message=\"{}\"/>", escape(reason)),
+                        "    </testcase>".to_string(),
+                    ]),
+                }
+            }
+            lines.push(format!(
+                "    <system-out>{}</system-out>",
+                escape(&result.stdout)
+            ));
+            lines.push(format!(
+                "    <system-err>{}</system-err>",
+                escape(&result.stderr)
+            ));
+            lines.push("  </testsuite>".to_string());
+        }
+        lines.push("</testsuites>".to_string());
+
+        lines.join("\n") + "\n"
+    }
+
+    fn tap(&self) -> String {
+        let cases = self
+            .results
+            .iter()
+            .flat_map(|result| {
+                TestCase::from_result(result, self.timeout)
+                    .into_iter()
+                    .map(move |case| (result, case))
+            })
+            .collect::<Vec<_>>();
+
+        let mut lines = vec!["TAP version 13".to_string(), format!("1..{}", cases.len())];
+        for (number, (result, case)) in cases.iter().enumerate() {
+            let description = match case.name == result.name {
+                true => result.name.clone(),
+                false => format!("{}: {}", result.name, case.name),
+            };
+            lines.push(match &case.status {
+                CaseStatus::Passed => format!("ok {} - {}", number + 1, description),
+                CaseStatus::Failed(_) => format!("not ok {} - {}", number + 1, description),
+                CaseStatus::Skipped(reason) => {
+                    format!("ok {} - {} # SKIP {}", number + 1, description, reason)
+                        .trim_end()
+                        .to_string()
+                }
+            });
+
+            let mut diagnostics = vec![];
+            if let Some(duration) = case.duration {
+                diagnostics.push(format!(
+                    "duration_ms: {:.3}",
+                    duration.as_secs_f64() * 1000.0
+                ));
+            }
+            if let CaseStatus::Failed(message) = &case.status {
+                diagnostics.push(format!("message: {}", yaml_string(message)));
+                for (name, output) in [("stdout", &result.stdout), ("stderr", &result.stderr)] {
+                    if !output.is_empty() {
+                        diagnostics.push(format!("{}: |", name));
+                        diagnostics.extend(output.lines().map(|line| format!("  {}", line)));
+                    }
+                }
+            }
+            if !diagnostics.is_empty() {
+                lines.push("  ---".to_string());
+                lines.extend(diagnostics.iter().map(|line| format!("  {}", line)));
+                lines.push("  ...".to_string());
+            }
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+/// `text` escaped for XML attributes and contents, without the control
+/// characters XML 1.0 does not allow.
+pub fn escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t' | '\r'))
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&apos;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// `text` as a double-quoted YAML string.
+fn yaml_string(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
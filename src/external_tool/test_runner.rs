@@ -0,0 +1,201 @@
+use std::{
+    collections::VecDeque,
+    io::{ErrorKind, Read},
+    os::unix::process::CommandExt,
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex, mpsc},
+    time::{Duration, Instant},
+};
+
+/// How long the output of a finished test is still read while processes it
+/// left behind hold its pipes open.
+const DRAIN_GRACE: Duration = Duration::from_millis(500);
+
+pub enum Outcome {
+    Passed,
+    /// Exited unsuccessfully, with the reason (exit code or signal).
+    Failed(String),
+    TimedOut,
+}
+
+pub struct TestResult {
+    pub name: String,
+    pub outcome: Outcome,
+    pub duration: Duration,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        matches!(self.outcome, Outcome::Passed)
+    }
+}
+
+/// Runs test executables on a fixed number of worker threads, killing those
+/// that run longer than the timeout.
+///
+/// Like [`JobPool`](super::job_pool::JobPool), outputs are captured and handed
+/// back whole, so results of parallel tests never interleave.
+pub struct TestRunner {
+    workers: usize,
+    timeout: Duration,
+}
+
+impl TestRunner {
+    pub fn new(workers: usize, timeout: Duration) -> Self {
+        Self {
+            workers: workers.max(1),
+            timeout,
+        }
+    }
+
+    /// Runs every `(name, command)` test, reporting each result through
+    /// `on_result` on the calling thread as soon as it finishes.
+    pub fn run<F>(&self, tests: Vec<(String, Command)>, mut on_result: F)
+    where
+        F: FnMut(TestResult),
+    {
+        let queue = Mutex::new(tests.into_iter().collect::<VecDeque<_>>());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.workers {
+                let tx = tx.clone();
+                let queue = &queue;
+
+                scope.spawn(move || {
+                    loop {
+                        let Some((name, command)) =
+                            queue.lock().ok().and_then(|mut queue| queue.pop_front())
+                        else {
+                            break;
+                        };
+
+                        if tx.send(self.run_one(name, command)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(tx);
+
+            for result in rx {
+                on_result(result);
+            }
+        });
+    }
+
+    fn run_one(&self, name: String, mut command: Command) -> TestResult {
+        let start = Instant::now();
+        let result = |outcome, stdout, stderr| TestResult {
+            name: name.clone(),
+            outcome,
+            duration: start.elapsed(),
+            stdout,
+            stderr,
+        };
+
+        // Each test leads its own process group, so that what it starts can be killed with it.
+        let mut child = match command
+            .process_group(0)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                return result(
+                    Outcome::Failed(format!("failed to execute: {}", e)),
+                    "".to_string(),
+                    "".to_string(),
+                );
+            }
+        };
+
+        // Pipes are drained while waiting, so a chatty test cannot block on a full pipe.
+        let stdout = child.stdout.take().map(Drain::start);
+        let stderr = child.stderr.take().map(Drain::start);
+
+        let outcome = loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => break Outcome::Passed,
+                Ok(Some(status)) => {
+                    break Outcome::Failed(match status.code() {
+                        Some(code) => format!("exit code {}", code),
+                        None => format!("{}", status),
+                    });
+                }
+                Ok(None) if start.elapsed() >= self.timeout => {
+                    Self::kill_group(&mut child);
+                    child.wait().ok();
+                    break Outcome::TimedOut;
+                }
+                Ok(None) => std::thread::sleep(Duration::from_millis(5)),
+                Err(e) => break Outcome::Failed(format!("failed to wait for the test: {}", e)),
+            }
+        };
+
+        let deadline = Instant::now() + DRAIN_GRACE;
+        let collect = |drain: Option<Drain>| {
+            drain
+                .map(|drain| drain.collect(deadline))
+                .unwrap_or_default()
+        };
+        result(outcome, collect(stdout), collect(stderr))
+    }
+
+    /// Kills the test and every process it started in its group.
+    fn kill_group(child: &mut Child) {
+        let killed = i32::try_from(child.id())
+            // SAFETY: `kill` only sends a signal, it does not touch our memory.
+            .is_ok_and(|pid| unsafe { libc::kill(-pid, libc::SIGKILL) } == 0);
+        if !killed {
+            child.kill().ok();
+        }
+    }
+}
+
+/// A pipe read to the end on its own thread.
+struct Drain {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    finished: mpsc::Receiver<()>,
+}
+
+impl Drain {
+    fn start<R: Read + Send + 'static>(mut pipe: R) -> Self {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let (done, finished) = mpsc::channel();
+        let output = buffer.clone();
+        std::thread::spawn(move || {
+            let mut chunk = [0; 4096];
+            loop {
+                match pipe.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(read) => match output.lock() {
+                        Ok(mut output) => output.extend_from_slice(&chunk[..read]),
+                        Err(_) => break,
+                    },
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(_) => break,
+                }
+            }
+            done.send(()).ok();
+        });
+
+        Self { buffer, finished }
+    }
+
+    /// What was read until the pipe closed, or until `deadline` if something
+    /// still holds it open.
+    fn collect(self, deadline: Instant) -> String {
+        self.finished
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .ok();
+        self.buffer
+            .lock()
+            .map(|buffer| String::from_utf8_lossy(&buffer).to_string())
+            .unwrap_or_default()
+    }
+}
@@ -10,6 +10,10 @@ pub fn error() -> String {
     "\x1B[31;1merror\x1B[0m".to_string()
 }
 
+pub fn warning() -> String {
+    "\x1B[33;1mwarning\x1B[0m".to_string()
+}
+
 #[allow(unused)]
 pub fn info(title: &str) -> String {
     let title_len = title.len();
@@ -21,30 +25,52 @@ pub struct Progress {
     title: String,
     total: usize,
     current: usize,
+    in_flight: Vec<String>,
+    quiet: bool,
 }
 
 impl Progress {
     pub fn new(title: &str, total: usize) -> Self {
-        print!("{} [>{}] 0/{}", info(title), " ".repeat(25), total);
-        let _ = std::io::stdout().flush();
+        let progress = Self {
+            title: title.to_string(),
+            total,
+            current: 0,
+            in_flight: vec![],
+            quiet: false,
+        };
+        progress.draw();
+
+        progress
+    }
 
+    /// A progress that draws no bar and prints its messages to stderr, for
+    /// when stdout is reserved for machine-readable output.
+    pub fn quiet(title: &str, total: usize) -> Self {
         Self {
             title: title.to_string(),
             total,
             current: 0,
+            in_flight: vec![],
+            quiet: true,
         }
     }
 
-    fn clear_line() {
+    fn clear_line(&self) {
+        if self.quiet {
+            return;
+        }
         print!("\r\x1B[K");
     }
 
-    pub fn next(&mut self, message: &str) {
-        Self::clear_line();
-        println!("{} ", message);
-
-        self.current += 1;
-        let percentage = self.current as f64 / self.total as f64;
+    fn draw(&self) {
+        if self.quiet {
+            return;
+        }
+        let percentage = if self.total == 0 {
+            1.0
+        } else {
+            self.current as f64 / self.total as f64
+        };
         let filled_length = (percentage * 25.0).round() as usize;
         let bar = format!(
             "[{}>{}] {}/{}",
@@ -53,11 +79,57 @@ impl Progress {
             self.current,
             self.total
         );
-        print!("\r{} {}", info(&self.title), bar);
+        let in_flight = if self.in_flight.is_empty() {
+            "".to_string()
+        } else {
+            format!(": {}", self.in_flight.join(", "))
+        };
+        print!("\r{} {}{}", info(&self.title), bar, in_flight);
+        let _ = std::io::stdout().flush();
+    }
+
+    /// Prints a line above the bar.
+    pub fn println(&self, message: &str) {
+        if self.quiet {
+            eprintln!("{}", message);
+            return;
+        }
+        self.clear_line();
+        println!("{} ", message);
+        self.draw();
+    }
+
+    /// Prints captured tool output (e.g. compiler diagnostics) above the bar.
+    pub fn eprint(&self, output: &str) {
+        if self.quiet {
+            eprint!("{}", output);
+            return;
+        }
+        self.clear_line();
         let _ = std::io::stdout().flush();
+        eprint!("{}", output);
+        self.draw();
+    }
+
+    /// Marks a job as running.
+    pub fn start(&mut self, job: &str) {
+        self.in_flight.push(job.to_string());
+        self.clear_line();
+        self.draw();
+    }
+
+    /// Marks a running job as done and advances the bar.
+    pub fn done(&mut self, job: &str) {
+        if let Some(pos) = self.in_flight.iter().position(|j| j == job) {
+            self.in_flight.remove(pos);
+        }
+        self.current += 1;
+        self.clear_line();
+        self.draw();
     }
 
     pub fn finish(self) {
-        Self::clear_line();
+        self.clear_line();
+        let _ = std::io::stdout().flush();
     }
 }
@@ -0,0 +1,280 @@
+use crate::{
+    absolute_path::AbsolutePath,
+    manifest::{Manifest, dependency::Dependency, features::FeatureRequest},
+    package::Package,
+    storage::Storage,
+};
+use serde::{Deserialize, Serialize};
+
+/// `Tailor.lock`, the dependency graph of a package or of every member of a
+/// workspace:
+///
+/// ```toml
+/// version = 1
+///
+/// [[package]]
+/// name = "app"
+/// version = "0.1.0"
+/// dependencies = ["zlib 1.3.1"]
+/// dev-dependencies = ["unity 2.6.0"]
+///
+/// [[package]]
+/// name = "zlib"
+/// version = "1.3.1"
+/// source = "git+https://github.com/madler/zlib#v1.3.1"
+/// checksum = "9a93b2b7dfdac77ceba5a558a580e74667dd6fede4585b91eefb60f03b72df23"
+/// ```
+///
+/// Packages built in place (the package itself, workspace members) have no
+/// `source`, and list every package of their graph as resolved with their
+/// default features, the packages only their tests and examples need under
+/// `dev-dependencies`. Registry packages are `registry`, git ones
+/// `git+<url>#<revision>` and other local ones `path+<path>`, relative to the
+/// lockfile when inside its directory; registry and git packages record the
+/// checksum of their contents.
+///
+/// A build only updates the members it resolved, and only when it resolved
+/// them with their default features; a build without tests or examples keeps
+/// their locked dev-dependencies, unless they declare none anymore. A registry or git package must resolve to
+/// its locked version and contents (see [`Lockfile::check`]).
+#[derive(Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(skip)]
+    root: AbsolutePath,
+    #[serde(skip)]
+    members: Vec<AbsolutePath>,
+    version: u32,
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<String>,
+    #[serde(
+        default,
+        rename = "dev-dependencies",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    dev_dependencies: Vec<String>,
+}
+
+impl LockedPackage {
+    /// How members refer to the package, e.g. `zlib 1.3.1`.
+    fn id(&self) -> String {
+        format!("{} {}", self.name, self.version)
+    }
+}
+
+impl Lockfile {
+    /// The `Tailor.lock` of `root`, where `members` are the packages built in
+    /// place; empty when there is none yet.
+    pub fn load(root: &AbsolutePath, members: &[AbsolutePath]) -> Result<Self, String> {
+        let path = root.inner().join("Tailor.lock");
+        let mut lockfile = match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str::<Self>(&content)
+                .map_err(|e| format!("failed to parse {}: {}", path.display(), e))?,
+            Err(_) => Self {
+                root: AbsolutePath::default(),
+                members: vec![],
+                version: 1,
+                package: vec![],
+            },
+        };
+        lockfile.root = root.clone();
+        lockfile.members = members.to_vec();
+
+        Ok(lockfile)
+    }
+
+    /// Checks that `manifest`, resolved for `dependency`, is the locked
+    /// version and contents of a registry or git package.
+    pub fn check(&self, dependency: &Dependency, manifest: &Manifest) -> Result<(), String> {
+        let source = self.source(dependency);
+        let Some(locked) = self.package.iter().find(|locked| {
+            locked.name == dependency.name()
+                && locked.source == source
+                && match dependency {
+                    Dependency::Registry { version, .. } => locked.version == *version,
+                    Dependency::Git { .. } => true,
+                    Dependency::Local { .. } => false,
+                }
+        }) else {
+            return Ok(());
+        };
+
+        let changed = match (
+            locked.version == manifest.version(),
+            &locked.checksum,
+            Storage::checksum(manifest.location()),
+        ) {
+            (false, _, _) => format!("version {}", manifest.version()),
+            (true, Some(locked), Some(checksum)) if *locked != checksum => {
+                "different contents".to_string()
+            }
+            _ => return Ok(()),
+        };
+        Err(format!(
+            "{} from {} is locked at version {} in Tailor.lock, but resolved to {}; remove its entry from Tailor.lock to accept the change",
+            dependency.name(),
+            source.unwrap_or_default(),
+            locked.version,
+            changed
+        ))
+    }
+
+    /// Locks the graph `package` resolved to, when it was resolved with the
+    /// root's default features or the root is not locked yet.
+    pub fn add(&mut self, package: &Package) {
+        let manifest = package.manifest();
+        let locked = self
+            .package
+            .iter()
+            .any(|locked| locked.source.is_none() && locked.name == manifest.name());
+        let mut enabled = manifest.enabled_features().to_vec();
+        enabled.sort();
+        let mut default = manifest
+            .features()
+            .enable(&FeatureRequest::default())
+            .unwrap_or_default();
+        default.sort();
+        if locked && enabled != default {
+            return;
+        }
+
+        let mut dependencies = vec![];
+        let mut dev_dependencies = vec![];
+        for (dependency, dep_manifest) in package.sources() {
+            let entry = LockedPackage {
+                name: dependency.name().to_string(),
+                version: dep_manifest.version().to_string(),
+                source: self.source(dependency),
+                checksum: match dependency {
+                    Dependency::Local { .. } => None,
+                    _ => Storage::checksum(dep_manifest.location()),
+                },
+                dependencies: vec![],
+                dev_dependencies: vec![],
+            };
+            match package.is_dev_only(dep_manifest) {
+                true => dev_dependencies.push(entry.id()),
+                false => dependencies.push(entry.id()),
+            }
+            self.lock(entry);
+        }
+
+        if let Some(index) = self
+            .package
+            .iter()
+            .position(|locked| locked.source.is_none() && locked.name == manifest.name())
+        {
+            let locked = self.package.remove(index);
+            if !package.has_dev_dependencies() && !manifest.dev_dependencies().is_empty() {
+                dev_dependencies = locked.dev_dependencies;
+                dev_dependencies.retain(|id| !dependencies.contains(id));
+            }
+        }
+        dependencies.sort();
+        dependencies.dedup();
+        dev_dependencies.sort();
+        dev_dependencies.dedup();
+        self.package.push(LockedPackage {
+            name: manifest.name().to_string(),
+            version: manifest.version().to_string(),
+            source: None,
+            checksum: None,
+            dependencies,
+            dev_dependencies,
+        });
+    }
+
+    /// Adds a resolved package, replacing the one locked with the same name
+    /// and version. A member resolved as a dependency keeps its locked graph.
+    fn lock(&mut self, entry: LockedPackage) {
+        if entry.source.is_none() {
+            if !self
+                .package
+                .iter()
+                .any(|locked| locked.source.is_none() && locked.name == entry.name)
+            {
+                self.package.push(entry);
+            }
+            return;
+        }
+
+        self.package
+            .retain(|locked| locked.source.is_none() || locked.id() != entry.id());
+        self.package.push(entry);
+    }
+
+    fn source(&self, dependency: &Dependency) -> Option<String> {
+        match dependency {
+            Dependency::Registry { .. } => Some("registry".to_string()),
+            Dependency::Git { url, revision, .. } => Some(format!("git+{}#{}", url, revision)),
+            Dependency::Local { path, .. } => {
+                let path = path
+                    .inner()
+                    .canonicalize()
+                    .unwrap_or(path.inner().to_path_buf());
+                if self.members.iter().any(|member| member.inner() == path) {
+                    return None;
+                }
+                let relative = path.strip_prefix(self.root.inner()).unwrap_or(&path);
+                Some(format!("path+{}", relative.display()))
+            }
+        }
+    }
+
+    /// Names of the packages built in place, from their manifests.
+    fn member_names(&self) -> Vec<String> {
+        self.members
+            .iter()
+            .filter_map(|member| std::fs::read_to_string(member.inner().join("Tailor.toml")).ok())
+            .filter_map(|content| content.parse::<toml::Table>().ok())
+            .filter_map(|table| table.get("name")?.as_str().map(String::from))
+            .collect()
+    }
+
+    /// Writes `Tailor.lock`, leaving it untouched when its contents did not
+    /// change. Members no longer in the workspace, and packages no member
+    /// depends on anymore, are dropped.
+    pub fn write(mut self) -> Result<(), String> {
+        let members = self.member_names();
+        self.package
+            .retain(|locked| locked.source.is_some() || members.contains(&locked.name));
+        let used = self
+            .package
+            .iter()
+            .filter(|locked| locked.source.is_none())
+            .flat_map(|member| {
+                member
+                    .dependencies
+                    .iter()
+                    .chain(&member.dev_dependencies)
+                    .cloned()
+            })
+            .collect::<Vec<_>>();
+        self.package
+            .retain(|locked| locked.source.is_none() || used.contains(&locked.id()));
+        self.package.sort();
+        self.package.dedup();
+
+        let path = self.root.inner().join("Tailor.lock");
+        let content = format!(
+            "# This file is generated by tailor; do not edit.\n{}",
+            toml::to_string(&self).map_err(|e| format!("failed to render Tailor.lock: {}", e))?
+        );
+        if std::fs::read_to_string(&path).is_ok_and(|current| current == content) {
+            return Ok(());
+        }
+        std::fs::write(&path, content)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))
+    }
+}
@@ -5,12 +5,16 @@ mod command;
 mod config;
 mod external_tool;
 mod fmt;
+mod lockfile;
 mod manifest;
-mod mode;
 mod package;
 mod storage;
+mod toolchain;
+mod workspace;
 
 use crate::command::clean_pkg::CleanPkg;
+use crate::command::info_pkg::InfoPkg;
+use crate::command::test_pkg::TestPkg;
 use crate::command::{build_pkg::BuildPkg, new_pkg::NewPkg, run_pkg::RunPkg};
 use crate::config::Config;
 use crate::{command::Command, fmt::error};
@@ -26,7 +30,9 @@ fn main() {
         &mut NewPkg::default(),
         &mut BuildPkg::default(),
         &mut RunPkg::default(),
+        &mut TestPkg::default(),
         &mut CleanPkg::default(),
+        &mut InfoPkg::default(),
     ];
     let args = args().collect::<Vec<String>>();
 
@@ -43,14 +49,41 @@ fn main() {
 
     println!("C language package manager\n");
     println!("Usage: tailor [COMMAND] [OPTIONS] <path>\n");
-    println!("Options:");
-    println!("  --bin       Create a binary package (only for `new` command) (default)");
-    println!("  --lib       Create a library package (only for `new` command)");
-    println!("  --debug     Build (or run) in debug mode (default)");
-    println!("  --release   Build (or run) in release mode\n");
     println!("Commands:");
     println!("  new         Create a new package");
     println!("  build       Build the package");
     println!("  run         Run the package");
+    println!("  test        Build and run the package's tests, e.g. `test -- parser`");
     println!("  clean       Clean the build artifacts");
+    println!("  info        Show the package, its dependencies, their features and options\n");
+    println!("Options of `new`:");
+    println!("  --bin       Create a binary package (default)");
+    println!("  --lib       Create a library package");
+    println!("  --header-only  Create a header-only library package\n");
+    println!("Options of `build`, `run` and `test`:");
+    println!("  --debug     Use the `debug` profile (default)");
+    println!("  --release   Use the `release` profile");
+    println!("  --profile   Use a profile defined in Tailor.toml");
+    println!("  --target    Cross-compile with a toolchain from `toolchains/<name>.toml`");
+    println!("  -j, --jobs  Number of parallel compile jobs (default: number of CPUs)");
+    println!("  --sanitize  Build with sanitizers, e.g. `address,undefined`");
+    println!("  -p, --package  Select a member of the workspace");
+    println!("  --features  Enable features of the package, e.g. `fast,zlib`");
+    println!("  --no-default-features  Do not enable the package's `default` feature");
+    println!("  --all-features    Enable every feature of the package");
+    println!("  --message-format  `human` (default) or `json` diagnostics and artifacts");
+    println!("  -v, --verbose     Show warnings from dependencies\n");
+    println!("Options of `build` and `run`:");
+    println!("  --bin       Build or run one binary of the package, e.g. `--bin bench`");
+    println!("  --example   Build or run an example of the package, e.g. `--example demo`\n");
+    println!("Options of `test`:");
+    println!("  --timeout   Seconds a test may run before it fails (default: 60)");
+    println!("  --format    Test report: `human` (default), `junit` or `tap`");
+    println!("  --output    File to write the test report to (default: stdout)");
+    println!("  --coverage  Measure the coverage of the tests");
+    println!("  --coverage-deps  Also measure the coverage of dependencies\n");
+    println!("Options of `clean`:");
+    println!("  --debug, --release, --profile  Only clean that profile");
+    println!("  --target    Only clean the artifacts of that toolchain");
+    println!("  -p, --package  Only clean that member of the workspace");
 }
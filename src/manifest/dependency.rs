@@ -1,6 +1,9 @@
 use crate::{
     absolute_path::AbsolutePath,
-    manifest::kv::{KeyValue, Value},
+    manifest::{
+        features::FeatureRequest,
+        kv::{KeyValue, Value},
+    },
     storage::Storage,
 };
 
@@ -10,21 +13,35 @@ pub enum Dependency {
         name: String,
         version: String,
         options: Vec<KeyValue>,
+        optional: bool,
+        features: FeatureRequest,
     },
     Git {
         name: String,
         url: String,
         revision: String,
         options: Vec<KeyValue>,
+        optional: bool,
+        features: FeatureRequest,
     },
     Local {
         name: String,
         path: AbsolutePath,
         options: Vec<KeyValue>,
+        optional: bool,
+        features: FeatureRequest,
     },
 }
 
 impl Dependency {
+    pub fn name(&self) -> &str {
+        match self {
+            Dependency::Registry { name, .. } => name,
+            Dependency::Git { name, .. } => name,
+            Dependency::Local { name, .. } => name,
+        }
+    }
+
     pub fn options(&self) -> &[KeyValue] {
         match self {
             Dependency::Registry { options, .. } => options,
@@ -33,14 +50,33 @@ impl Dependency {
         }
     }
 
+    /// Whether the dependency is only used when a feature enables it.
+    pub fn optional(&self) -> bool {
+        match self {
+            Dependency::Registry { optional, .. } => *optional,
+            Dependency::Git { optional, .. } => *optional,
+            Dependency::Local { optional, .. } => *optional,
+        }
+    }
+
+    /// Features the dependent requests from this dependency.
+    pub fn features(&self) -> &FeatureRequest {
+        match self {
+            Dependency::Registry { features, .. } => features,
+            Dependency::Git { features, .. } => features,
+            Dependency::Local { features, .. } => features,
+        }
+    }
+
+    /// Parses the `key` table, `dependencies` or `dev-dependencies`.
     pub fn parse_dependencies(
         toml_table: &toml::Table,
         location: &AbsolutePath,
+        key: &str,
     ) -> Result<Vec<Self>, String> {
         let mut dependencies = vec![];
 
-        let Some(dependencies_table) = toml_table.get("dependencies").and_then(|v| v.as_table())
-        else {
+        let Some(dependencies_table) = toml_table.get(key).and_then(|v| v.as_table()) else {
             return Ok(dependencies);
         };
 
@@ -52,23 +88,24 @@ impl Dependency {
         Ok(dependencies)
     }
 
-    fn parse_options(name: &str, root: &toml::Table) -> Vec<KeyValue> {
+    fn parse_options(name: &str, root: &toml::Table) -> Result<Vec<KeyValue>, String> {
         let mut options = vec![];
         let Some(dep_options_table) = root
             .get(name)
             .and_then(|v| v.as_table())
             .and_then(|table| table.get("options").and_then(|opts| opts.as_table()))
         else {
-            return options;
+            return Ok(options);
         };
 
         for (opt_name, opt_value) in dep_options_table {
-            let value = match opt_value {
-                toml::Value::String(s) => Value::String(s.clone()),
-                toml::Value::Float(f) => Value::Float(*f),
-                toml::Value::Integer(i) => Value::Integer(*i),
-                toml::Value::Boolean(b) => Value::Boolean(*b),
-                _ => continue,
+            let Some(value) = Value::from_toml(opt_value) else {
+                return Err(format!(
+                    "option `{}` of '{}' must be a string, number or boolean, found {}",
+                    opt_name,
+                    name,
+                    opt_value.type_str()
+                ));
             };
             options.push(KeyValue {
                 key: opt_name.clone(),
@@ -76,7 +113,7 @@ impl Dependency {
             });
         }
 
-        options
+        Ok(options)
     }
 
     fn parse_single_dependency(
@@ -89,7 +126,9 @@ impl Dependency {
             return Ok(Dependency::Registry {
                 name: name.to_string(),
                 version: version.to_string(),
-                options: Self::parse_options(name, root),
+                options: Self::parse_options(name, root)?,
+                optional: false,
+                features: FeatureRequest::default(),
             });
         }
 
@@ -100,6 +139,49 @@ impl Dependency {
             ));
         };
 
+        let optional = match table.get("optional") {
+            Some(value) => value.as_bool().ok_or(format!(
+                "Dependency '{}': 'optional' must be a boolean",
+                name
+            ))?,
+            None => false,
+        };
+        let features = FeatureRequest {
+            features: match table.get("features") {
+                Some(value) => value
+                    .as_array()
+                    .and_then(|features| {
+                        features
+                            .iter()
+                            .map(|feature| feature.as_str().map(String::from))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .ok_or(format!(
+                        "Dependency '{}': 'features' must be a list of strings",
+                        name
+                    ))?,
+                None => vec![],
+            },
+            default_features: match table.get("default-features") {
+                Some(value) => value.as_bool().ok_or(format!(
+                    "Dependency '{}': 'default-features' must be a boolean",
+                    name
+                ))?,
+                None => true,
+            },
+            all_features: false,
+        };
+
+        if let Some(version) = table.get("version").and_then(|v| v.as_str()) {
+            return Ok(Dependency::Registry {
+                name: name.to_string(),
+                version: version.to_string(),
+                options: Self::parse_options(name, root)?,
+                optional,
+                features,
+            });
+        }
+
         if let Some(url) = table.get("git").and_then(|v| v.as_str()) {
             let revision = table
                 .get("rev")
@@ -111,7 +193,9 @@ impl Dependency {
                 name: name.to_string(),
                 url: url.to_string(),
                 revision,
-                options: Self::parse_options(name, root),
+                options: Self::parse_options(name, root)?,
+                optional,
+                features,
             });
         }
 
@@ -119,12 +203,14 @@ impl Dependency {
             return Ok(Dependency::Local {
                 name: name.to_string(),
                 path: location.join(path),
-                options: Self::parse_options(name, root),
+                options: Self::parse_options(name, root)?,
+                optional,
+                features,
             });
         }
 
         Err(format!(
-            "Dependency '{}' has an invalid format; expected 'git' or 'path' fields",
+            "Dependency '{}' has an invalid format; expected 'version', 'git' or 'path' fields",
             name
         ))
     }
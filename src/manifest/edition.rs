@@ -16,3 +16,11 @@ impl Edition {
         }
     }
 }
+
+impl std::fmt::Display for Edition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Edition::Edition2026_1 => write!(f, "2026.1"),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use crate::manifest::dependency::Dependency;
+
+/// Features requested for a package: by the command line for the root
+/// package, by a dependent's `[dependencies]` entry otherwise.
+#[derive(Clone)]
+pub struct FeatureRequest {
+    pub features: Vec<String>,
+    pub default_features: bool,
+    pub all_features: bool,
+}
+
+impl Default for FeatureRequest {
+    fn default() -> Self {
+        Self {
+            features: vec![],
+            default_features: true,
+            all_features: false,
+        }
+    }
+}
+
+/// Compile-time feature toggles from the `[features]` table:
+///
+/// ```toml
+/// [features]
+/// default = ["std"]
+/// std = []
+/// compression = ["dep:zlib", "fast"]   # enables the optional `zlib` dependency
+/// fast = ["zlib/asm"]                  # enables feature `asm` of `zlib`
+/// ```
+///
+/// Like cargo, an optional dependency that no feature names as `dep:<name>`
+/// gets an implicit feature of the same name enabling it.
+#[derive(Clone, Default)]
+pub struct Features {
+    declared: Vec<(String, Vec<String>)>,
+}
+
+impl Features {
+    pub fn parse_features(
+        toml_table: &toml::Table,
+        dependencies: &[Dependency],
+    ) -> Result<Self, String> {
+        let mut declared = vec![];
+        if let Some(value) = toml_table.get("features") {
+            let table = value
+                .as_table()
+                .ok_or("'features' must be a table".to_string())?;
+            for (name, entries) in table {
+                if name.is_empty()
+                    || !name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    return Err(format!(
+                        "invalid feature name `{}`; use letters, digits, `-` and `_`",
+                        name
+                    ));
+                }
+                let entries = entries
+                    .as_array()
+                    .and_then(|entries| {
+                        entries
+                            .iter()
+                            .map(|entry| entry.as_str().map(String::from))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .ok_or(format!("feature `{}` must be a list of strings", name))?;
+                declared.push((name.clone(), entries));
+            }
+        }
+
+        let optional = dependencies
+            .iter()
+            .filter(|dependency| dependency.optional())
+            .map(|dependency| dependency.name())
+            .collect::<Vec<_>>();
+        for name in &optional {
+            let explicit = declared
+                .iter()
+                .flat_map(|(_, entries)| entries)
+                .any(|entry| entry.strip_prefix("dep:") == Some(name));
+            if !explicit && !declared.iter().any(|(feature, _)| feature == name) {
+                declared.push((name.to_string(), vec![format!("dep:{}", name)]));
+            }
+        }
+
+        for (feature, entries) in &declared {
+            for entry in entries {
+                let valid = if let Some(dependency) = entry.strip_prefix("dep:") {
+                    optional.contains(&dependency)
+                } else if let Some((dependency, _)) = entry.split_once('/') {
+                    dependencies.iter().any(|d| d.name() == dependency)
+                } else {
+                    declared.iter().any(|(name, _)| name == entry)
+                };
+                if !valid {
+                    return Err(format!(
+                        "feature `{}` enables `{}`, which is neither a feature, an optional dependency (`dep:<name>`) nor a dependency feature (`<dependency>/<feature>`)",
+                        feature, entry
+                    ));
+                }
+            }
+        }
+
+        Ok(Self { declared })
+    }
+
+    /// Declared features, including the implicit ones of optional dependencies.
+    pub fn names(&self) -> Vec<&str> {
+        self.declared
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Features enabled by `request`, including those they enable in turn.
+    pub fn enable(&self, request: &FeatureRequest) -> Result<Vec<String>, String> {
+        let mut pending = request.features.clone();
+        if request.default_features && self.declared.iter().any(|(name, _)| name == "default") {
+            pending.push("default".to_string());
+        }
+        if request.all_features {
+            pending.extend(self.names().into_iter().map(String::from));
+        }
+
+        let mut enabled: Vec<String> = vec![];
+        while let Some(feature) = pending.pop() {
+            if enabled.contains(&feature) {
+                continue;
+            }
+            let Some((_, entries)) = self.declared.iter().find(|(name, _)| *name == feature) else {
+                return Err(format!(
+                    "no feature `{}`; {}",
+                    feature,
+                    match self.declared.is_empty() {
+                        true => "the package declares no features".to_string(),
+                        false => format!(
+                            "available features are {}",
+                            self.names()
+                                .iter()
+                                .map(|name| format!("`{}`", name))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    }
+                ));
+            };
+            pending.extend(
+                entries
+                    .iter()
+                    .filter(|entry| !entry.starts_with("dep:") && !entry.contains('/'))
+                    .cloned(),
+            );
+            enabled.push(feature);
+        }
+
+        enabled.sort();
+        Ok(enabled)
+    }
+
+    /// Whether the `enabled` features activate the optional dependency `name`.
+    pub fn activates(&self, enabled: &[String], name: &str) -> bool {
+        self.entries(enabled).any(|entry| {
+            entry.strip_prefix("dep:") == Some(name)
+                || entry.split_once('/').map(|(dependency, _)| dependency) == Some(name)
+        })
+    }
+
+    /// Features of dependency `name` that the `enabled` features turn on.
+    pub fn dependency_features(&self, enabled: &[String], name: &str) -> Vec<String> {
+        self.entries(enabled)
+            .filter_map(|entry| entry.split_once('/'))
+            .filter(|(dependency, _)| *dependency == name)
+            .map(|(_, feature)| feature.to_string())
+            .collect()
+    }
+
+    fn entries<'a>(&'a self, enabled: &'a [String]) -> impl Iterator<Item = &'a String> {
+        self.declared
+            .iter()
+            .filter(|(name, _)| enabled.contains(name))
+            .flat_map(|(_, entries)| entries)
+    }
+
+    /// `-DTAILOR_FEATURE_<NAME>` for an enabled feature.
+    pub fn define(feature: &str) -> String {
+        format!("-D{}", Self::variable(feature))
+    }
+
+    /// `TAILOR_FEATURE_<NAME>`, with the name uppercased and `-` replaced by `_`.
+    pub fn variable(feature: &str) -> String {
+        format!(
+            "TAILOR_FEATURE_{}",
+            feature.to_uppercase().replace('-', "_")
+        )
+    }
+}
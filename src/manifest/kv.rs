@@ -1,10 +1,10 @@
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct KeyValue {
     pub key: String,
     pub value: Value,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum Value {
     String(String),
     Integer(i64),
@@ -12,19 +12,65 @@ pub enum Value {
     Boolean(bool),
 }
 
+impl Value {
+    /// Converts a scalar TOML value; arrays, tables and datetimes yield `None`.
+    pub fn from_toml(value: &toml::Value) -> Option<Self> {
+        match value {
+            toml::Value::String(s) => Some(Value::String(s.clone())),
+            toml::Value::Float(f) => Some(Value::Float(*f)),
+            toml::Value::Integer(i) => Some(Value::Integer(*i)),
+            toml::Value::Boolean(b) => Some(Value::Boolean(*b)),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{:?}", s),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Boolean(b) => write!(f, "{}", b),
+        }
+    }
+}
+
 impl KeyValue {
-    pub fn to_define(&self) -> String {
+    /// Renders the option as a single `-D` compiler argument, with the key
+    /// uppercased. A `false` option `declared` in an `[options-schema]` is
+    /// defined to `0`; an undeclared one yields no argument, leaving the
+    /// name undefined.
+    ///
+    /// The argument is passed to the compiler as-is (no shell involved), so
+    /// string values only need escaping as C string literals.
+    pub fn to_define(&self, declared: bool) -> String {
+        let key = self.key.to_uppercase();
         match &self.value {
-            Value::String(s) => format!("-D{}=\"\\\"{}\\\"\"", self.key.to_uppercase(), s),
-            Value::Integer(i) => format!("-D{}={}", self.key.to_uppercase(), i),
-            Value::Float(f) => format!("-D{}={}", self.key.to_uppercase(), f),
-            Value::Boolean(b) => {
-                if *b {
-                    format!("-D{}", self.key.to_uppercase())
-                } else {
-                    "".to_string()
-                }
-            }
+            Value::String(s) => format!("-D{}={}", key, Self::c_string(s)),
+            Value::Integer(i) => format!("-D{}={}", key, i),
+            Value::Float(f) => format!("-D{}={}", key, f),
+            Value::Boolean(true) => format!("-D{}", key),
+            Value::Boolean(false) if declared => format!("-D{}=0", key),
+            Value::Boolean(false) => "".to_string(),
         }
     }
+
+    /// Renders the option as a `#define` line of a generated header, with
+    /// `false` handled like on the command line.
+    pub fn to_define_line(&self, declared: bool) -> String {
+        let key = self.key.to_uppercase();
+        match &self.value {
+            Value::String(s) => format!("#define {} {}", key, Self::c_string(s)),
+            Value::Integer(i) => format!("#define {} {}", key, i),
+            Value::Float(f) => format!("#define {} {}", key, f),
+            Value::Boolean(true) => format!("#define {} 1", key),
+            Value::Boolean(false) if declared => format!("#define {} 0", key),
+            Value::Boolean(false) => format!("/* #undef {} */", key),
+        }
+    }
+
+    fn c_string(s: &str) -> String {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
 }
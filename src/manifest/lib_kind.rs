@@ -0,0 +1,32 @@
+/// Kind of library artifact produced for a `lib` root package.
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum LibKind {
+    #[default]
+    Static,
+    Shared,
+    Both,
+}
+
+impl LibKind {
+    pub fn parse_lib_kind(toml_table: &toml::Table) -> Result<Self, String> {
+        let lib_kind_str = toml_table
+            .get("lib-kind")
+            .and_then(|v| v.as_str())
+            .unwrap_or("static");
+
+        match lib_kind_str {
+            "static" => Ok(LibKind::Static),
+            "shared" => Ok(LibKind::Shared),
+            "both" => Ok(LibKind::Both),
+            _ => Err(format!("Unknown library kind: {}", lib_kind_str)),
+        }
+    }
+
+    pub fn is_static(&self) -> bool {
+        matches!(self, LibKind::Static | LibKind::Both)
+    }
+
+    pub fn is_shared(&self) -> bool {
+        matches!(self, LibKind::Shared | LibKind::Both)
+    }
+}
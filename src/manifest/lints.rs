@@ -0,0 +1,94 @@
+#[derive(Clone, Copy, PartialEq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl TryFrom<&str> for LintLevel {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "allow" => Ok(LintLevel::Allow),
+            "warn" => Ok(LintLevel::Warn),
+            "deny" => Ok(LintLevel::Deny),
+            _ => Err(format!(
+                "unknown lint level `{}`; expected allow, warn or deny",
+                value
+            )),
+        }
+    }
+}
+
+/// Warning policy from the `[lints]` table, keyed by compiler warning name:
+///
+/// ```toml
+/// [lints]
+/// extra = "allow"            # -Wno-extra
+/// shadow = "warn"            # -Wshadow
+/// implicit-fallthrough = "deny"
+/// ```
+///
+/// `all` and `extra` are `warn` unless overridden. The groups come first so
+/// that the named warnings refine them.
+#[derive(Clone)]
+pub struct Lints {
+    levels: Vec<(String, LintLevel)>,
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        Self {
+            levels: vec![
+                ("all".to_string(), LintLevel::Warn),
+                ("extra".to_string(), LintLevel::Warn),
+            ],
+        }
+    }
+}
+
+impl Lints {
+    /// Warning groups that contain other warnings, as opposed to a single named warning.
+    pub const GROUPS: [&str; 2] = ["all", "extra"];
+
+    pub fn parse_lints(toml_table: &toml::Table) -> Result<Self, String> {
+        let mut lints = Self::default();
+        let Some(value) = toml_table.get("lints") else {
+            return Ok(lints);
+        };
+        let table = value
+            .as_table()
+            .ok_or("'lints' must be a table".to_string())?;
+
+        for (name, level) in table {
+            if name.is_empty()
+                || name.starts_with("no-")
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '=')
+            {
+                return Err(format!(
+                    "invalid warning name `{}`; use the name after `-W`, e.g. \"shadow\"",
+                    name
+                ));
+            }
+            let level = level
+                .as_str()
+                .ok_or(format!("level of `{}` must be a string", name))
+                .and_then(LintLevel::try_from)?;
+
+            match lints.levels.iter_mut().find(|(n, _)| n == name) {
+                Some((_, current)) => *current = level,
+                None => lints.levels.push((name.clone(), level)),
+            }
+        }
+
+        Ok(lints)
+    }
+
+    /// Warning names with their level, groups first.
+    pub fn levels(&self) -> &[(String, LintLevel)] {
+        &self.levels
+    }
+}
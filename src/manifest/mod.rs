@@ -1,31 +1,110 @@
 use crate::{
     absolute_path::AbsolutePath,
     manifest::{
-        dependency::Dependency, edition::Edition, package_type::PackageType,
+        dependency::Dependency,
+        edition::Edition,
+        features::{FeatureRequest, Features},
+        kv::{KeyValue, Value},
+        lib_kind::LibKind,
+        lints::Lints,
+        options_schema::OptionsSchema,
+        package_type::PackageType,
         pattern_path::PatternPath,
+        profile::Profile,
+        system_dependency::SystemDependency,
+        target::{Target, TargetKind},
     },
 };
+use std::path::{Path, PathBuf};
 
 pub mod dependency;
 pub mod edition;
+pub mod features;
 pub mod kv;
+pub mod lib_kind;
+pub mod lints;
+pub mod options_schema;
 pub mod package_type;
 pub mod pattern_path;
+pub mod profile;
+pub mod sanitizer;
+pub mod system_dependency;
+pub mod target;
 
 #[derive(Clone)]
 pub struct Manifest {
     name: String,
     version: String,
+    edition: Edition,
     type_: PackageType,
+    lib_kind: LibKind,
     dependencies: Vec<Dependency>,
+    dev_dependencies: Vec<Dependency>,
     sources: Vec<PatternPath>,
+    main: Option<PathBuf>,
+    bins: Vec<Target>,
+    examples: Vec<Target>,
+    tests: Vec<Target>,
     includes: Vec<PatternPath>,
+    dev_includes: Vec<PatternPath>,
     compiler: String,
+    std: Option<String>,
+    cflags: Vec<String>,
+    ldflags: Vec<String>,
+    links: Vec<String>,
+    system_dependencies: Vec<SystemDependency>,
+    system_cflags: Vec<String>,
+    system_libs: Vec<String>,
+    profiles: Vec<Profile>,
+    options: Vec<KeyValue>,
+    options_schema: Option<OptionsSchema>,
+    package_options: Vec<(String, Vec<KeyValue>)>,
+    features: Features,
+    enabled_features: Vec<String>,
+    lints: Lints,
+    build_script: Option<PathBuf>,
+    config_header: bool,
+    location: AbsolutePath,
 }
 
 impl Manifest {
+    /// Top-level keys of `Tailor.toml`; any other table may set the options of
+    /// a package with `[<name>.options]`.
+    const KEYS: [&str; 25] = [
+        "name",
+        "version",
+        "edition",
+        "type",
+        "lib-kind",
+        "sources",
+        "main",
+        "bin",
+        "includes",
+        "compiler",
+        "std",
+        "cflags",
+        "ldflags",
+        "links",
+        "dependencies",
+        "dev-dependencies",
+        "system-dependencies",
+        "profile",
+        "options",
+        "options-schema",
+        "features",
+        "lints",
+        "build",
+        "config-header",
+        "workspace",
+    ];
+
+    /// Whether dependents can use the package, i.e. it is a library or a header-only one.
     pub fn is_library(&self) -> bool {
-        self.type_ == PackageType::Library
+        matches!(self.type_, PackageType::Library | PackageType::HeaderOnly)
+    }
+
+    pub fn is_header_only(&self) -> bool {
+        self.type_ == PackageType::HeaderOnly
     }
 
     pub fn full_name(&self) -> String {
@@ -40,8 +119,28 @@ impl Manifest {
         &self.name
     }
 
+    pub fn edition(&self) -> &Edition {
+        &self.edition
+    }
+
+    /// Dependencies in use: optional ones only when an enabled feature activates them.
     pub fn dependencies(&self) -> Vec<Dependency> {
-        self.dependencies.clone()
+        self.dependencies
+            .iter()
+            .filter(|dependency| {
+                !dependency.optional()
+                    || self
+                        .features
+                        .activates(&self.enabled_features, dependency.name())
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Dependencies of the package's tests and examples only, from
+    /// `[dev-dependencies]`; they are never resolved for dependents.
+    pub fn dev_dependencies(&self) -> &[Dependency] {
+        &self.dev_dependencies
     }
 
     fn resolve_source_pattern(pattern: &str) -> Vec<String> {
@@ -69,13 +168,88 @@ impl Manifest {
         vec![pattern]
     }
 
+    /// Sources of the package, without the `main` of its other binaries, examples and tests.
     pub fn sources(&self) -> Vec<String> {
+        let target_mains = self
+            .bins
+            .iter()
+            .chain(&self.examples)
+            .chain(&self.tests)
+            .filter_map(|target| target.main().canonicalize().ok())
+            .collect::<Vec<_>>();
+
         self.sources
             .iter()
             .flat_map(|src| Self::resolve_source_pattern(src.to_string().as_str()))
+            .filter(|source| {
+                Path::new(source)
+                    .canonicalize()
+                    .ok()
+                    .is_none_or(|source| !target_mains.contains(&source))
+            })
             .collect()
     }
 
+    /// Source of the default binary's `main`, from the `main` key (default
+    /// `src/main.c`); `None` for libraries.
+    pub fn main(&self) -> Option<&Path> {
+        self.main.as_deref()
+    }
+
+    /// Binaries from `[[bin]]` and `src/bin/*.c`.
+    pub fn bins(&self) -> &[Target] {
+        &self.bins
+    }
+
+    /// Examples from `examples/*.c`.
+    pub fn examples(&self) -> &[Target] {
+        &self.examples
+    }
+
+    /// Tests from `tests/*.c`.
+    pub fn tests(&self) -> &[Target] {
+        &self.tests
+    }
+
+    /// The binary or example named `name`.
+    pub fn target(&self, kind: TargetKind, name: &str) -> Result<&Target, String> {
+        let targets = match kind {
+            TargetKind::Bin => &self.bins,
+            TargetKind::Example => &self.examples,
+            TargetKind::Test => &self.tests,
+        };
+
+        targets
+            .iter()
+            .find(|target| target.name() == name)
+            .ok_or(match targets.is_empty() {
+                true => format!("no {} target named `{}`; the package has none", kind, name),
+                false => format!(
+                    "no {} target named `{}`; available {} targets are {}",
+                    kind,
+                    name,
+                    kind,
+                    targets
+                        .iter()
+                        .map(|target| format!("`{}`", target.name()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            })
+    }
+
+    /// The main source of `target` followed by its extra sources.
+    pub fn target_sources(&self, target: &Target) -> Vec<String> {
+        let mut sources = vec![target.main().to_string_lossy().to_string()];
+        sources.extend(
+            target
+                .sources()
+                .iter()
+                .flat_map(|src| Self::resolve_source_pattern(src.to_string().as_str())),
+        );
+        sources
+    }
+
     pub fn includes(&self) -> &[PatternPath] {
         &self.includes
     }
@@ -84,14 +258,147 @@ impl Manifest {
         self.includes = includes;
     }
 
+    /// Include directories of the dev-dependencies, for tests and examples only.
+    pub fn dev_includes(&self) -> &[PatternPath] {
+        &self.dev_includes
+    }
+
+    pub fn set_dev_includes(&mut self, includes: Vec<PatternPath>) {
+        self.dev_includes = includes;
+    }
+
     pub fn pkg_type(&self) -> PackageType {
         self.type_
     }
 
+    pub fn lib_kind(&self) -> LibKind {
+        self.lib_kind
+    }
+
     pub fn compiler(&self) -> &str {
         &self.compiler
     }
 
+    /// C standard the package's sources are compiled with (e.g. `c11`).
+    pub fn std(&self) -> Option<&str> {
+        self.std.as_deref()
+    }
+
+    /// Extra flags for compiling this package's own sources.
+    pub fn cflags(&self) -> &[String] {
+        &self.cflags
+    }
+
+    /// Extra flags for the final link of any package depending on this one.
+    pub fn ldflags(&self) -> &[String] {
+        &self.ldflags
+    }
+
+    /// Libraries from `links`, linked as `-l<name>`.
+    pub fn links(&self) -> &[String] {
+        &self.links
+    }
+
+    pub fn system_dependencies(&self) -> &[SystemDependency] {
+        &self.system_dependencies
+    }
+
+    /// `pkg-config` compile flags of this package's and its direct dependencies' system dependencies.
+    pub fn system_cflags(&self) -> &[String] {
+        &self.system_cflags
+    }
+
+    /// Link flags for `links` and system dependencies, needed by the final link.
+    pub fn system_libs(&self) -> &[String] {
+        &self.system_libs
+    }
+
+    pub fn set_system_flags(&mut self, cflags: Vec<String>, libs: Vec<String>) {
+        self.system_cflags = cflags;
+        self.system_libs = libs;
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile, String> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name() == name)
+            .ok_or(format!("profile `{}` is not defined", name))
+    }
+
+    /// Options of the package, passed as defines to its own sources only.
+    ///
+    /// Parsed from `[options]` as defaults, then replaced by the resolved set
+    /// once dependents' requests are known (see `Package::load_from_manifest`).
+    pub fn options(&self) -> &[KeyValue] {
+        &self.options
+    }
+
+    pub fn set_options(&mut self, options: Vec<KeyValue>) {
+        self.options = options;
+    }
+
+    /// Options the package accepts, when it declares `[options-schema]`.
+    pub fn options_schema(&self) -> Option<&OptionsSchema> {
+        self.options_schema.as_ref()
+    }
+
+    /// Options this manifest sets for package `name` in `[<name>.options]`.
+    pub fn options_for(&self, name: &str) -> &[KeyValue] {
+        self.package_options
+            .iter()
+            .find(|(package, _)| package == name)
+            .map(|(_, options)| options.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Features the package declares in `[features]`.
+    pub fn features(&self) -> &Features {
+        &self.features
+    }
+
+    /// Enabled features, each passed to the package's own sources as a
+    /// `TAILOR_FEATURE_<NAME>` define (see `Package::load_from_manifest`).
+    pub fn enabled_features(&self) -> &[String] {
+        &self.enabled_features
+    }
+
+    pub fn set_enabled_features(&mut self, features: Vec<String>) {
+        self.enabled_features = features;
+    }
+
+    /// Features this package requests from `dependency`: those of its
+    /// `[dependencies]` entry plus `<dependency>/<feature>` of enabled features.
+    pub fn feature_request_for(&self, dependency: &Dependency) -> FeatureRequest {
+        let mut request = dependency.features().clone();
+        request.features.extend(
+            self.features
+                .dependency_features(&self.enabled_features, dependency.name()),
+        );
+        request
+    }
+
+    /// Warning policy from `[lints]`. Dependencies are built without warnings
+    /// unless `--verbose` is given, and never fail on them.
+    pub fn lints(&self) -> &Lints {
+        &self.lints
+    }
+
+    /// Script run before compiling the package, from the `build` key.
+    pub fn build_script(&self) -> Option<&Path> {
+        self.build_script.as_deref()
+    }
+
+    /// Whether options, features and metadata go to a generated
+    /// `<name>_config.h` instead of `-D` flags, from the `config-header` key.
+    pub fn config_header(&self) -> bool {
+        self.config_header
+    }
+
+    /// Directory containing the package's `Tailor.toml`.
+    pub fn location(&self) -> &AbsolutePath {
+        &self.location
+    }
+
     pub fn from_file(content: &str, location: &AbsolutePath) -> Result<Self, String> {
         let toml_table = content
             .parse::<toml::Table>()
@@ -99,17 +406,51 @@ impl Manifest {
 
         let name = Self::parse_name(&toml_table)?;
         let version = Self::parse_version(&toml_table)?;
-        let _edition = Edition::parse_edition(&toml_table)
+        let edition = Edition::parse_edition(&toml_table)
             .map_err(|e| format!("Failed to parse edition: {}", e))?;
 
-        let type_ = toml_table
-            .get("type")
-            .and_then(|v| v.as_str())
-            .unwrap_or("bin");
-        let dependencies = Dependency::parse_dependencies(&toml_table, location)
+        let type_ = match toml_table.get("type").and_then(|v| v.as_str()) {
+            None | Some("bin") => PackageType::Binary,
+            Some("lib") => PackageType::Library,
+            Some("header-only") => PackageType::HeaderOnly,
+            Some(type_) => return Err(format!("Unknown package type: {}", type_)),
+        };
+        let lib_kind = LibKind::parse_lib_kind(&toml_table)
+            .map_err(|e| format!("Failed to parse lib-kind: {}", e))?;
+        let dependencies = Dependency::parse_dependencies(&toml_table, location, "dependencies")
             .map_err(|e| format!("Failed to parse dependencies: {}", e))?;
-        let sources = PatternPath::parse_paths(&toml_table, location, "sources", "src/*.c")
-            .map_err(|e| format!("Failed to parse sources: {}", e))?;
+        let dev_dependencies =
+            Dependency::parse_dependencies(&toml_table, location, "dev-dependencies")
+                .map_err(|e| format!("Failed to parse dev-dependencies: {}", e))?;
+        if let Some(optional) = dev_dependencies.iter().find(|dep| dep.optional()) {
+            return Err(format!(
+                "Failed to parse dev-dependencies: `{}` cannot be optional",
+                optional.name()
+            ));
+        }
+        let sources = match type_ {
+            PackageType::HeaderOnly if toml_table.contains_key("sources") => {
+                return Err("header-only packages cannot have 'sources'".to_string());
+            }
+            PackageType::HeaderOnly => vec![],
+            _ => PatternPath::parse_paths(&toml_table, location, "sources", "src/*.c")
+                .map_err(|e| format!("Failed to parse sources: {}", e))?,
+        };
+        let main = match (type_, toml_table.get("main")) {
+            (PackageType::Binary, Some(main)) => Some(
+                location.inner().join(
+                    main.as_str()
+                        .ok_or("'main' must be a path to a source".to_string())?,
+                ),
+            ),
+            (PackageType::Binary, None) => Some(location.inner().join("src/main.c")),
+            (_, Some(_)) => return Err("only bin packages have a 'main' source".to_string()),
+            (_, None) => None,
+        };
+        let bins = Target::parse_bins(&toml_table, location)
+            .map_err(|e| format!("Failed to parse bin targets: {}", e))?;
+        let examples = Target::discover_examples(location);
+        let tests = Target::discover_tests(location);
         let includes = PatternPath::parse_paths(&toml_table, location, "includes", "include/")
             .map_err(|e| format!("Failed to parse includes: {}", e))?;
         let compiler = toml_table
@@ -117,21 +458,113 @@ impl Manifest {
             .and_then(|v| v.as_str())
             .unwrap_or("gcc")
             .to_string();
+        let std = Self::parse_std(&toml_table)?;
+        let cflags = Self::parse_string_list(&toml_table, "cflags")?;
+        let ldflags = Self::parse_string_list(&toml_table, "ldflags")?;
+        let links = Self::parse_string_list(&toml_table, "links")?;
+        if let Some(link) = links.iter().find(|link| {
+            link.is_empty() || link.starts_with('-') || link.contains(char::is_whitespace)
+        }) {
+            return Err(format!(
+                "Invalid library name '{}' in 'links'; use the name without `-l`, e.g. \"m\"",
+                link
+            ));
+        }
+        let system_dependencies = SystemDependency::parse_system_dependencies(&toml_table)
+            .map_err(|e| format!("Failed to parse system dependencies: {}", e))?;
+        let profiles = Profile::parse_profiles(&toml_table)
+            .map_err(|e| format!("Failed to parse profiles: {}", e))?;
+        let options = match toml_table.get("options") {
+            Some(value) => Self::parse_options(
+                value
+                    .as_table()
+                    .ok_or("'options' must be a table".to_string())?,
+            )
+            .map_err(|e| format!("Failed to parse options: {}", e))?,
+            None => vec![],
+        };
+        let options_schema = OptionsSchema::parse_options_schema(&toml_table)
+            .map_err(|e| format!("Failed to parse options schema: {}", e))?;
+        // With a schema, `[options]` may only refine the declared defaults.
+        let options = match &options_schema {
+            Some(schema) => {
+                let mut defaults = schema.defaults();
+                for option in options {
+                    let option = schema
+                        .validate(&option)
+                        .map_err(|e| format!("Failed to parse options: {}", e))?;
+                    defaults.retain(|o| o.key != option.key);
+                    defaults.push(option);
+                }
+                defaults
+            }
+            None => options,
+        };
+        let mut package_options = vec![];
+        for (name, value) in &toml_table {
+            if let Some(table) = value
+                .as_table()
+                .and_then(|table| table.get("options"))
+                .and_then(|options| options.as_table())
+                && !Self::KEYS.contains(&name.as_str())
+            {
+                let options = Self::parse_options(table)
+                    .map_err(|e| format!("Failed to parse options of `{}`: {}", name, e))?;
+                package_options.push((name.clone(), options));
+            }
+        }
+        let features = Features::parse_features(&toml_table, &dependencies)
+            .map_err(|e| format!("Failed to parse features: {}", e))?;
+        let lints =
+            Lints::parse_lints(&toml_table).map_err(|e| format!("Failed to parse lints: {}", e))?;
+        let build_script = toml_table
+            .get("build")
+            .map(|v| {
+                v.as_str()
+                    .map(|script| location.inner().join(script))
+                    .ok_or("'build' must be a path to a script".to_string())
+            })
+            .transpose()?;
+        let config_header = match toml_table.get("config-header") {
+            Some(value) => value
+                .as_bool()
+                .ok_or("'config-header' must be a boolean".to_string())?,
+            None => false,
+        };
 
         Ok(Self {
             name,
             version,
-            type_: if type_ == "bin" {
-                PackageType::Binary
-            } else if type_ == "lib" {
-                PackageType::Library
-            } else {
-                return Err(format!("Unknown package type: {}", type_));
-            },
+            edition,
+            type_,
+            lib_kind,
             dependencies,
+            dev_dependencies,
             sources,
+            main,
+            bins,
+            examples,
+            tests,
             includes,
+            dev_includes: vec![],
             compiler,
+            std,
+            cflags,
+            ldflags,
+            links,
+            system_dependencies,
+            system_cflags: vec![],
+            system_libs: vec![],
+            profiles,
+            options,
+            options_schema,
+            package_options,
+            features,
+            enabled_features: vec![],
+            lints,
+            build_script,
+            config_header,
+            location: location.clone(),
         })
     }
 
@@ -150,4 +583,56 @@ impl Manifest {
             .ok_or("Missing 'version' field".to_string())
             .map(|s| s.to_string())
     }
+
+    fn parse_std(toml_table: &toml::Table) -> Result<Option<String>, String> {
+        let Some(value) = toml_table.get("std") else {
+            return Ok(None);
+        };
+
+        let std = value.as_str().ok_or("'std' must be a string".to_string())?;
+        let known = ["89", "90", "99", "11", "17", "18", "2x", "23"]
+            .iter()
+            .any(|year| std == format!("c{}", year) || std == format!("gnu{}", year));
+        if !known {
+            return Err(format!(
+                "Unknown C standard '{}'; expected e.g. \"c99\", \"c11\", \"c17\" or \"gnu11\"",
+                std
+            ));
+        }
+
+        Ok(Some(std.to_string()))
+    }
+
+    fn parse_options(table: &toml::Table) -> Result<Vec<KeyValue>, String> {
+        table
+            .iter()
+            .map(|(key, value)| {
+                Value::from_toml(value)
+                    .map(|value| KeyValue {
+                        key: key.clone(),
+                        value,
+                    })
+                    .ok_or(format!(
+                        "option `{}` must be a string, number or boolean",
+                        key
+                    ))
+            })
+            .collect()
+    }
+
+    fn parse_string_list(toml_table: &toml::Table, key: &str) -> Result<Vec<String>, String> {
+        let Some(value) = toml_table.get(key) else {
+            return Ok(vec![]);
+        };
+
+        value
+            .as_array()
+            .and_then(|items| {
+                items
+                    .iter()
+                    .map(|item| item.as_str().map(String::from))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or(format!("'{}' must be a list of strings", key))
+    }
 }
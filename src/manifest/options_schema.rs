@@ -0,0 +1,282 @@
+use crate::manifest::kv::{KeyValue, Value};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OptionType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+impl TryFrom<&str> for OptionType {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "string" => Ok(OptionType::String),
+            "integer" => Ok(OptionType::Integer),
+            "float" => Ok(OptionType::Float),
+            "boolean" => Ok(OptionType::Boolean),
+            _ => Err(format!(
+                "unknown type `{}`; expected string, integer, float or boolean",
+                value
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for OptionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionType::String => write!(f, "string"),
+            OptionType::Integer => write!(f, "integer"),
+            OptionType::Float => write!(f, "float"),
+            OptionType::Boolean => write!(f, "boolean"),
+        }
+    }
+}
+
+/// One option declared in `[options-schema.<name>]`.
+#[derive(Clone)]
+pub struct OptionSchema {
+    pub name: String,
+    pub type_: OptionType,
+    pub default: Option<Value>,
+    pub values: Vec<Value>,
+    pub range: Option<(f64, f64)>,
+    pub description: Option<String>,
+}
+
+impl OptionSchema {
+    fn parse(name: &str, table: &toml::Table) -> Result<Self, String> {
+        let type_ = table
+            .get("type")
+            .ok_or("missing 'type'".to_string())?
+            .as_str()
+            .ok_or("'type' must be a string".to_string())
+            .and_then(OptionType::try_from)?;
+
+        let mut option = Self {
+            name: name.to_string(),
+            type_,
+            default: None,
+            values: vec![],
+            range: None,
+            description: None,
+        };
+
+        for (key, value) in table {
+            match key.as_str() {
+                "type" => {}
+                "default" => option.default = Some(option.typed_toml(value)?),
+                "values" => {
+                    option.values = value
+                        .as_array()
+                        .ok_or("'values' must be a list".to_string())?
+                        .iter()
+                        .map(|value| option.typed_toml(value))
+                        .collect::<Result<Vec<_>, _>>()?;
+                }
+                "range" => {
+                    if !matches!(type_, OptionType::Integer | OptionType::Float) {
+                        return Err(format!("'range' does not apply to {} options", type_));
+                    }
+                    let bound = |value: &toml::Value| match value {
+                        toml::Value::Integer(i) => Some(*i as f64),
+                        toml::Value::Float(f) => Some(*f),
+                        _ => None,
+                    };
+                    let range = value
+                        .as_array()
+                        .and_then(|bounds| match bounds.as_slice() {
+                            [min, max] => Some((bound(min)?, bound(max)?)),
+                            _ => None,
+                        })
+                        .ok_or("'range' must be a list of two numbers, e.g. [0, 9]".to_string())?;
+                    if range.0 > range.1 {
+                        return Err("'range' minimum is greater than its maximum".to_string());
+                    }
+                    option.range = Some(range);
+                }
+                "description" => {
+                    option.description = Some(
+                        value
+                            .as_str()
+                            .ok_or("'description' must be a string".to_string())?
+                            .to_string(),
+                    );
+                }
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+        }
+
+        if let Some(default) = &option.default {
+            option.check(default)?;
+        }
+
+        Ok(option)
+    }
+
+    fn typed_toml(&self, value: &toml::Value) -> Result<Value, String> {
+        Value::from_toml(value)
+            .ok_or(format!(
+                "expected a {}, found {}",
+                self.type_,
+                value.type_str()
+            ))
+            .and_then(|value| self.typed(&value))
+    }
+
+    /// Converts a value to this option's type; integers are accepted for floats.
+    fn typed(&self, value: &Value) -> Result<Value, String> {
+        match (self.type_, value) {
+            (OptionType::String, Value::String(_))
+            | (OptionType::Integer, Value::Integer(_))
+            | (OptionType::Float, Value::Float(_))
+            | (OptionType::Boolean, Value::Boolean(_)) => Ok(value.clone()),
+            (OptionType::Float, Value::Integer(i)) => Ok(Value::Float(*i as f64)),
+            _ => Err(format!(
+                "expected {} {}, found `{}`",
+                match self.type_ {
+                    OptionType::Integer => "an",
+                    _ => "a",
+                },
+                self.type_,
+                value
+            )),
+        }
+    }
+
+    /// Checks a value of the right type against the allowed values and range.
+    fn check(&self, value: &Value) -> Result<(), String> {
+        if !self.values.is_empty() && !self.values.contains(value) {
+            return Err(format!(
+                "{} is not one of {}",
+                value,
+                self.values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        if let Some((min, max)) = self.range {
+            let number = match value {
+                Value::Integer(i) => *i as f64,
+                Value::Float(f) => *f,
+                _ => return Ok(()),
+            };
+            if number < min || number > max {
+                return Err(format!("{} is out of range {} to {}", value, min, max));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Options a library accepts, declared by its author in `[options-schema]`:
+///
+/// ```toml
+/// [options-schema.level]
+/// type = "integer"
+/// default = 6
+/// range = [0, 9]
+/// description = "Compression level"
+///
+/// [options-schema.strategy]
+/// type = "string"
+/// values = ["default", "filtered", "rle"]
+/// ```
+#[derive(Clone)]
+pub struct OptionsSchema {
+    options: Vec<OptionSchema>,
+}
+
+impl OptionsSchema {
+    pub fn parse_options_schema(toml_table: &toml::Table) -> Result<Option<Self>, String> {
+        let Some(value) = toml_table.get("options-schema") else {
+            return Ok(None);
+        };
+        let table = value
+            .as_table()
+            .ok_or("'options-schema' must be a table".to_string())?;
+
+        let options = table
+            .iter()
+            .map(|(name, value)| {
+                value
+                    .as_table()
+                    .ok_or("must be a table".to_string())
+                    .and_then(|table| OptionSchema::parse(name, table))
+                    .map_err(|e| format!("option `{}`: {}", name, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // Names are uppercased into defines, so they must differ in more than case.
+        for (index, option) in options.iter().enumerate() {
+            if let Some(other) = options[..index]
+                .iter()
+                .find(|other| other.name.to_uppercase() == option.name.to_uppercase())
+            {
+                return Err(format!(
+                    "options `{}` and `{}` both define `{}`",
+                    other.name,
+                    option.name,
+                    option.name.to_uppercase()
+                ));
+            }
+        }
+
+        Ok(Some(Self { options }))
+    }
+
+    pub fn options(&self) -> &[OptionSchema] {
+        &self.options
+    }
+
+    /// Declared defaults, for options that have one.
+    pub fn defaults(&self) -> Vec<KeyValue> {
+        self.options
+            .iter()
+            .filter_map(|option| {
+                option.default.clone().map(|value| KeyValue {
+                    key: option.name.clone(),
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    /// Validates a requested option value, converting it to the declared type.
+    pub fn validate(&self, requested: &KeyValue) -> Result<KeyValue, String> {
+        let name = &requested.key;
+        let Some(option) = self.options.iter().find(|option| &option.name == name) else {
+            return Err(format!(
+                "unknown option `{}`; {}",
+                name,
+                match self.options.is_empty() {
+                    true => "the package declares no options".to_string(),
+                    false => format!(
+                        "available options are {}",
+                        self.options
+                            .iter()
+                            .map(|option| format!("`{}`", option.name))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                }
+            ));
+        };
+
+        let value = option
+            .typed(&requested.value)
+            .and_then(|value| option.check(&value).map(|_| value))
+            .map_err(|e| format!("invalid value for option `{}`: {}", name, e))?;
+
+        Ok(KeyValue {
+            key: name.clone(),
+            value,
+        })
+    }
+}
@@ -3,4 +3,7 @@ pub enum PackageType {
     #[default]
     Binary,
     Library,
+    /// A library made of headers only: it has no sources and is never linked,
+    /// but contributes include paths and defines to its dependents.
+    HeaderOnly,
 }
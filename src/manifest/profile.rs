@@ -0,0 +1,315 @@
+use crate::manifest::{
+    kv::{KeyValue, Value},
+    sanitizer::Sanitizer,
+};
+
+/// Build settings selected with `--profile <name>` (or `--debug`/`--release`).
+///
+/// `debug` and `release` are always available and may be tweaked with
+/// `[profile.debug]`/`[profile.release]`. Any other `[profile.<name>]` table
+/// must name the profile it `inherits` from. Scalar settings of the parent are
+/// overridden, `cflags`/`ldflags` are appended and `defines` are merged.
+#[derive(Clone)]
+pub struct Profile {
+    name: String,
+    opt_level: String,
+    debug: u8,
+    lto: bool,
+    warnings_as_errors: bool,
+    sanitizers: Vec<Sanitizer>,
+    coverage: bool,
+    cflags: Vec<String>,
+    ldflags: Vec<String>,
+    defines: Vec<KeyValue>,
+}
+
+impl Profile {
+    fn debug() -> Self {
+        Self {
+            name: "debug".to_string(),
+            opt_level: "g".to_string(),
+            debug: 2,
+            lto: false,
+            warnings_as_errors: false,
+            sanitizers: vec![],
+            coverage: false,
+            cflags: vec![],
+            ldflags: vec![],
+            defines: vec![],
+        }
+    }
+
+    fn release() -> Self {
+        Self {
+            name: "release".to_string(),
+            opt_level: "s".to_string(),
+            debug: 0,
+            lto: false,
+            warnings_as_errors: false,
+            sanitizers: vec![],
+            coverage: false,
+            cflags: vec![],
+            ldflags: vec![],
+            defines: vec![],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Optimization level as written after `-O` (`0`-`3`, `s`, `z`, `g` or `fast`).
+    pub fn opt_level(&self) -> &str {
+        &self.opt_level
+    }
+
+    /// Debug info level, from 0 (none) to 3.
+    pub fn debug_level(&self) -> u8 {
+        self.debug
+    }
+
+    pub fn lto(&self) -> bool {
+        self.lto
+    }
+
+    pub fn warnings_as_errors(&self) -> bool {
+        self.warnings_as_errors
+    }
+
+    pub fn sanitizers(&self) -> &[Sanitizer] {
+        &self.sanitizers
+    }
+
+    /// Adds sanitizers requested on the command line (`--sanitize`).
+    pub fn add_sanitizers(&mut self, sanitizers: &[Sanitizer]) {
+        for sanitizer in sanitizers {
+            if !self.sanitizers.contains(sanitizer) {
+                self.sanitizers.push(*sanitizer);
+            }
+        }
+    }
+
+    /// Whether the build is instrumented for coverage, with `tailor test --coverage`.
+    pub fn coverage(&self) -> bool {
+        self.coverage
+    }
+
+    /// Instruments the build for coverage; optimizations are turned off so
+    /// that the counts map onto the source lines.
+    pub fn set_coverage(&mut self, coverage: bool) {
+        self.coverage = coverage;
+        if coverage {
+            self.opt_level = "0".to_string();
+        }
+    }
+
+    pub fn cflags(&self) -> &[String] {
+        &self.cflags
+    }
+
+    pub fn ldflags(&self) -> &[String] {
+        &self.ldflags
+    }
+
+    pub fn defines(&self) -> &[KeyValue] {
+        &self.defines
+    }
+
+    pub fn parse_profiles(toml_table: &toml::Table) -> Result<Vec<Self>, String> {
+        let empty = toml::Table::new();
+        let profiles_table = match toml_table.get("profile") {
+            Some(value) => value
+                .as_table()
+                .ok_or("'profile' must be a table".to_string())?,
+            None => &empty,
+        };
+
+        let mut names = vec!["debug".to_string(), "release".to_string()];
+        names.extend(
+            profiles_table
+                .keys()
+                .filter(|name| !names.contains(name))
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+
+        names
+            .iter()
+            .map(|name| Self::resolve(name, profiles_table, &mut vec![]))
+            .collect()
+    }
+
+    fn resolve(
+        name: &str,
+        profiles: &toml::Table,
+        stack: &mut Vec<String>,
+    ) -> Result<Self, String> {
+        if stack.iter().any(|n| n == name) {
+            return Err(format!(
+                "profile `{}` inherits from itself ({} -> {})",
+                name,
+                stack.join(" -> "),
+                name
+            ));
+        }
+        stack.push(name.to_string());
+
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "invalid profile name `{}`; use letters, digits, `-` and `_`",
+                name
+            ));
+        }
+
+        let table = match profiles.get(name) {
+            Some(value) => Some(
+                value
+                    .as_table()
+                    .ok_or(format!("profile `{}` must be a table", name))?,
+            ),
+            None => None,
+        };
+        let inherits = table
+            .and_then(|table| table.get("inherits"))
+            .map(|v| {
+                v.as_str()
+                    .ok_or(format!("profile `{}`: 'inherits' must be a string", name))
+            })
+            .transpose()?;
+
+        let mut profile = match (name, inherits) {
+            ("debug" | "release", Some(_)) => {
+                return Err(format!(
+                    "profile `{}` is built in and cannot inherit from another profile",
+                    name
+                ));
+            }
+            ("debug", None) => Self::debug(),
+            ("release", None) => Self::release(),
+            (_, None) => {
+                return Err(format!(
+                    "profile `{}` must set 'inherits' (e.g. inherits = \"release\")",
+                    name
+                ));
+            }
+            (_, Some(parent)) => {
+                if parent != "debug" && parent != "release" && !profiles.contains_key(parent) {
+                    return Err(format!(
+                        "profile `{}` inherits from undefined profile `{}`",
+                        name, parent
+                    ));
+                }
+                Self::resolve(parent, profiles, stack)?
+            }
+        };
+
+        profile.name = name.to_string();
+        if let Some(table) = table {
+            profile.apply(table)?;
+        }
+
+        Ok(profile)
+    }
+
+    fn apply(&mut self, table: &toml::Table) -> Result<(), String> {
+        let name = self.name.clone();
+
+        for (key, value) in table {
+            match key.as_str() {
+                "inherits" => {}
+                "opt-level" => {
+                    let level = match value {
+                        toml::Value::Integer(level) => level.to_string(),
+                        toml::Value::String(level) => level.clone(),
+                        _ => {
+                            return Err(format!(
+                                "profile `{}`: 'opt-level' must be a number or a string",
+                                name
+                            ));
+                        }
+                    };
+                    if !["0", "1", "2", "3", "s", "z", "g", "fast"].contains(&level.as_str()) {
+                        return Err(format!(
+                            "profile `{}`: invalid opt-level `{}`; expected 0, 1, 2, 3, \"s\", \"z\", \"g\" or \"fast\"",
+                            name, level
+                        ));
+                    }
+                    self.opt_level = level;
+                }
+                "debug" => {
+                    self.debug = match value {
+                        toml::Value::Boolean(true) => 2,
+                        toml::Value::Boolean(false) => 0,
+                        toml::Value::Integer(level @ 0..=3) => *level as u8,
+                        _ => {
+                            return Err(format!(
+                                "profile `{}`: 'debug' must be a boolean or a level from 0 to 3",
+                                name
+                            ));
+                        }
+                    };
+                }
+                "lto" => {
+                    self.lto = value
+                        .as_bool()
+                        .ok_or(format!("profile `{}`: 'lto' must be a boolean", name))?;
+                }
+                "warnings-as-errors" => {
+                    self.warnings_as_errors = value.as_bool().ok_or(format!(
+                        "profile `{}`: 'warnings-as-errors' must be a boolean",
+                        name
+                    ))?;
+                }
+                "sanitize" => {
+                    self.sanitizers = Self::parse_flags(&name, key, value)?
+                        .iter()
+                        .map(|sanitizer| {
+                            Sanitizer::try_from(sanitizer.as_str())
+                                .map_err(|e| format!("profile `{}`: {}", name, e))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                }
+                "cflags" => self.cflags.extend(Self::parse_flags(&name, key, value)?),
+                "ldflags" => self.ldflags.extend(Self::parse_flags(&name, key, value)?),
+                "defines" => {
+                    let defines = value
+                        .as_table()
+                        .ok_or(format!("profile `{}`: 'defines' must be a table", name))?;
+                    for (define, value) in defines {
+                        let value = Value::from_toml(value).ok_or(format!(
+                            "profile `{}`: define `{}` must be a string, number or boolean",
+                            name, define
+                        ))?;
+                        self.defines.retain(|kv| &kv.key != define);
+                        self.defines.push(KeyValue {
+                            key: define.clone(),
+                            value,
+                        });
+                    }
+                }
+                _ => return Err(format!("profile `{}`: unknown key '{}'", name, key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_flags(name: &str, key: &str, value: &toml::Value) -> Result<Vec<String>, String> {
+        value
+            .as_array()
+            .and_then(|flags| {
+                flags
+                    .iter()
+                    .map(|flag| flag.as_str().map(String::from))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or(format!(
+                "profile `{}`: '{}' must be a list of strings",
+                name, key
+            ))
+    }
+}
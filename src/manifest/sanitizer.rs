@@ -0,0 +1,59 @@
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sanitizer {
+    Address,
+    Undefined,
+    Thread,
+    Leak,
+    Memory,
+}
+
+impl Sanitizer {
+    pub const ALL: [Sanitizer; 5] = [
+        Sanitizer::Address,
+        Sanitizer::Undefined,
+        Sanitizer::Thread,
+        Sanitizer::Leak,
+        Sanitizer::Memory,
+    ];
+
+    /// Runtime options making the sanitizer stop the program with a nonzero
+    /// exit status on the first report, for sanitizers that do not by default.
+    pub fn runtime_options(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Sanitizer::Address => Some(("ASAN_OPTIONS", "halt_on_error=1:detect_leaks=1")),
+            Sanitizer::Undefined => Some(("UBSAN_OPTIONS", "halt_on_error=1:print_stacktrace=1")),
+            Sanitizer::Thread => Some(("TSAN_OPTIONS", "halt_on_error=1")),
+            Sanitizer::Leak | Sanitizer::Memory => None,
+        }
+    }
+}
+
+impl TryFrom<&str> for Sanitizer {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "address" => Ok(Sanitizer::Address),
+            "undefined" => Ok(Sanitizer::Undefined),
+            "thread" => Ok(Sanitizer::Thread),
+            "leak" => Ok(Sanitizer::Leak),
+            "memory" => Ok(Sanitizer::Memory),
+            _ => Err(format!(
+                "unknown sanitizer `{}`; expected address, undefined, thread, leak or memory",
+                value
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Sanitizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sanitizer::Address => write!(f, "address"),
+            Sanitizer::Undefined => write!(f, "undefined"),
+            Sanitizer::Thread => write!(f, "thread"),
+            Sanitizer::Leak => write!(f, "leak"),
+            Sanitizer::Memory => write!(f, "memory"),
+        }
+    }
+}
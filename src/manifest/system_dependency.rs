@@ -0,0 +1,94 @@
+/// A library installed on the system and located through `pkg-config`.
+///
+/// Declared in `[system-dependencies]` as `zlib = ">= 1.2.11"` (or `"*"` for
+/// any version). A bare version such as `"1.2"` means `>= 1.2`.
+#[derive(Clone)]
+pub struct SystemDependency {
+    name: String,
+    version: Option<(String, String)>,
+}
+
+impl SystemDependency {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Module specification understood by `pkg-config`, e.g. `zlib >= 1.2.11`.
+    pub fn module_spec(&self) -> String {
+        match &self.version {
+            Some((op, version)) => format!("{} {} {}", self.name, op, version),
+            None => self.name.clone(),
+        }
+    }
+
+    pub fn parse_system_dependencies(toml_table: &toml::Table) -> Result<Vec<Self>, String> {
+        let mut dependencies = vec![];
+
+        let Some(value) = toml_table.get("system-dependencies") else {
+            return Ok(dependencies);
+        };
+        let table = value
+            .as_table()
+            .ok_or("'system-dependencies' must be a table".to_string())?;
+
+        for (name, value) in table {
+            let requirement = match value {
+                toml::Value::String(requirement) => requirement.as_str(),
+                toml::Value::Table(table) => {
+                    table
+                        .get("version")
+                        .and_then(|v| v.as_str())
+                        .ok_or(format!(
+                            "System dependency '{}' must have a 'version' string",
+                            name
+                        ))?
+                }
+                _ => {
+                    return Err(format!(
+                        "System dependency '{}' has an invalid format; expected a version string or a table",
+                        name
+                    ));
+                }
+            };
+
+            dependencies.push(Self {
+                name: name.clone(),
+                version: Self::parse_requirement(name, requirement)?,
+            });
+        }
+
+        Ok(dependencies)
+    }
+
+    fn parse_requirement(
+        name: &str,
+        requirement: &str,
+    ) -> Result<Option<(String, String)>, String> {
+        let requirement = requirement.trim();
+        if requirement == "*" || requirement.is_empty() {
+            return Ok(None);
+        }
+
+        let (op, version) = ["!=", ">=", "<=", "=", ">", "<"]
+            .iter()
+            .find_map(|op| {
+                requirement
+                    .strip_prefix(op)
+                    .map(|version| (op.to_string(), version.trim()))
+            })
+            .unwrap_or((">=".to_string(), requirement));
+
+        let valid = !version.is_empty()
+            && version
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_');
+        if !valid {
+            return Err(format!(
+                "System dependency '{}' has an invalid version requirement '{}'",
+                name, requirement
+            ));
+        }
+
+        Ok(Some((op, version.to_string())))
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    absolute_path::AbsolutePath,
+    manifest::{Manifest, pattern_path::PatternPath},
+};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TargetKind {
+    Bin,
+    Example,
+    Test,
+}
+
+impl std::fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetKind::Bin => write!(f, "bin"),
+            TargetKind::Example => write!(f, "example"),
+            TargetKind::Test => write!(f, "test"),
+        }
+    }
+}
+
+/// An executable built from a package besides its default binary, linked
+/// with the package's own sources (but its default `main`) and dependencies.
+///
+/// Declared with `[[bin]]` tables, or discovered from `src/bin/*.c`,
+/// `examples/*.c` and `tests/*.c`:
+///
+/// ```toml
+/// [[bin]]
+/// name = "bench"
+/// main = "tools/bench.c"        # default: src/bin/<name>.c
+/// sources = ["tools/timer.c"]   # extra sources of this binary only
+/// ```
+#[derive(Clone)]
+pub struct Target {
+    kind: TargetKind,
+    name: String,
+    main: PathBuf,
+    sources: Vec<PatternPath>,
+}
+
+impl Target {
+    /// Whether the target links the package's dev-dependencies.
+    pub fn is_dev(&self) -> bool {
+        self.kind != TargetKind::Bin
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn main(&self) -> &Path {
+        &self.main
+    }
+
+    /// Extra sources of the target, as patterns to resolve.
+    pub fn sources(&self) -> &[PatternPath] {
+        &self.sources
+    }
+
+    /// `bin/<name>`, `examples/<name>` or `tests/<package>/<name>` inside the
+    /// build directory; tests of the members of a workspace often share names.
+    pub fn output_path(&self, build_path: &Path, package: &Manifest) -> PathBuf {
+        match self.kind {
+            TargetKind::Bin => build_path.join("bin").join(&self.name),
+            TargetKind::Example => build_path.join("examples").join(&self.name),
+            TargetKind::Test => build_path
+                .join("tests")
+                .join(package.full_name())
+                .join(&self.name),
+        }
+    }
+
+    pub fn parse_bins(
+        toml_table: &toml::Table,
+        location: &AbsolutePath,
+    ) -> Result<Vec<Self>, String> {
+        let mut bins: Vec<Self> = vec![];
+        if let Some(value) = toml_table.get("bin") {
+            let tables = value
+                .as_array()
+                .ok_or("'bin' must be an array of tables, written `[[bin]]`".to_string())?;
+            for table in tables {
+                let table = table
+                    .as_table()
+                    .ok_or("'bin' must be an array of tables, written `[[bin]]`".to_string())?;
+                let bin = Self::parse_bin(table, location)?;
+                if bins.iter().any(|other| other.name == bin.name) {
+                    return Err(format!("bin target `{}` is declared twice", bin.name));
+                }
+                bins.push(bin);
+            }
+        }
+
+        for (name, main) in Self::discover(location.inner(), "src/bin") {
+            if !bins.iter().any(|bin| bin.name == name || bin.main == main) {
+                bins.push(Self {
+                    kind: TargetKind::Bin,
+                    name,
+                    main,
+                    sources: vec![],
+                });
+            }
+        }
+
+        Ok(bins)
+    }
+
+    fn parse_bin(table: &toml::Table, location: &AbsolutePath) -> Result<Self, String> {
+        let name = table
+            .get("name")
+            .ok_or("a `[[bin]]` target is missing 'name'".to_string())?
+            .as_str()
+            .filter(|name| {
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            })
+            .ok_or("bin target names must be made of letters, digits, `-` and `_`".to_string())?
+            .to_string();
+
+        let main = match table.get("main") {
+            Some(main) => main
+                .as_str()
+                .ok_or(format!("bin target `{}`: 'main' must be a path", name))?
+                .to_string(),
+            None => format!("src/bin/{}.c", name),
+        };
+        let sources = match table.contains_key("sources") {
+            true => PatternPath::parse_paths(table, location, "sources", "")
+                .map_err(|e| format!("bin target `{}`: {}", name, e))?,
+            false => vec![],
+        };
+
+        for key in table.keys() {
+            if !["name", "main", "sources"].contains(&key.as_str()) {
+                return Err(format!("bin target `{}`: unknown key '{}'", name, key));
+            }
+        }
+
+        Ok(Self {
+            kind: TargetKind::Bin,
+            main: location.inner().join(main),
+            name,
+            sources,
+        })
+    }
+
+    pub fn discover_examples(location: &AbsolutePath) -> Vec<Self> {
+        Self::discover_kind(location, TargetKind::Example, "examples")
+    }
+
+    pub fn discover_tests(location: &AbsolutePath) -> Vec<Self> {
+        Self::discover_kind(location, TargetKind::Test, "tests")
+    }
+
+    fn discover_kind(location: &AbsolutePath, kind: TargetKind, directory: &str) -> Vec<Self> {
+        Self::discover(location.inner(), directory)
+            .into_iter()
+            .map(|(name, main)| Self {
+                kind,
+                name,
+                main,
+                sources: vec![],
+            })
+            .collect()
+    }
+
+    /// `(stem, path)` of every `.c` file directly in `directory`, sorted by name.
+    fn discover(location: &Path, directory: &str) -> Vec<(String, PathBuf)> {
+        let Ok(entries) = std::fs::read_dir(location.join(directory)) else {
+            return vec![];
+        };
+
+        let mut found = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "c"))
+            .filter_map(|path| {
+                let stem = path.file_stem()?.to_str()?.to_string();
+                Some((stem, path))
+            })
+            .collect::<Vec<_>>();
+        found.sort_by(|(a, _), (b, _)| a.cmp(b));
+        found
+    }
+}
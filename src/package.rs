@@ -1,78 +1,424 @@
 use crate::{
-    external_tool::registry::Registry,
-    manifest::{Manifest, kv::KeyValue, pattern_path::PatternPath},
+    external_tool::{
+        pkg_config::{PkgConfig, SystemLibrary},
+        registry::Registry,
+    },
+    lockfile::Lockfile,
+    manifest::{
+        Manifest, dependency::Dependency, features::FeatureRequest, kv::KeyValue,
+        pattern_path::PatternPath, system_dependency::SystemDependency,
+    },
     storage::Storage,
 };
 
 pub struct Package {
     manifest: Manifest,
     dependencies: Vec<Manifest>,
+    sources: Vec<(Dependency, Manifest)>,
+    dev_only: Vec<String>,
+    dev: bool,
 }
 
 impl Package {
-    pub fn load_from_manifest(mut manifest: Manifest, registry: &Registry) -> Result<Self, String> {
-        let mut open_list = manifest.dependencies().clone();
-        let mut closed_list = vec![];
-        let mut dependencies = vec![];
+    /// Loads the dependency graph of `manifest`, with `features` requested for the root.
+    ///
+    /// Features are unified like cargo does: a package used by several
+    /// dependents is built once, with the union of the features they request.
+    /// Optional dependencies are only part of the graph when enabled.
+    ///
+    /// Registry and git packages must resolve to their version and contents
+    /// locked in `lockfile`.
+    pub fn load_from_manifest(
+        manifest: Manifest,
+        features: &FeatureRequest,
+        registry: &Registry,
+        lockfile: &Lockfile,
+    ) -> Result<Self, String> {
+        Self::load(manifest, features, registry, lockfile, false)
+    }
 
-        while let Some(dependency) = open_list.pop() {
-            if closed_list.contains(&dependency) {
+    /// Like [`Package::load_from_manifest`], with the root's `[dev-dependencies]`
+    /// resolved as well, for building its tests and examples.
+    pub fn load_with_dev_dependencies(
+        manifest: Manifest,
+        features: &FeatureRequest,
+        registry: &Registry,
+        lockfile: &Lockfile,
+    ) -> Result<Self, String> {
+        Self::load(manifest, features, registry, lockfile, true)
+    }
+
+    fn load(
+        mut manifest: Manifest,
+        features: &FeatureRequest,
+        registry: &Registry,
+        lockfile: &Lockfile,
+        dev: bool,
+    ) -> Result<Self, String> {
+        let enabled = manifest
+            .features()
+            .enable(features)
+            .map_err(|e| format!("{}: {}", manifest.full_name(), e))?;
+        manifest.set_enabled_features(enabled);
+
+        let dev_dependencies = match dev {
+            true => manifest.dev_dependencies().to_vec(),
+            false => vec![],
+        };
+        let mut open_list = manifest
+            .dependencies()
+            .into_iter()
+            .chain(dev_dependencies.iter().cloned())
+            .map(|dependency| {
+                let request = manifest.feature_request_for(&dependency);
+                (dependency, manifest.full_name(), request)
+            })
+            .collect::<Vec<_>>();
+        let mut resolved: Vec<(String, Manifest)> = vec![];
+        let mut sources = vec![];
+
+        while let Some((dependency, dependent, request)) = open_list.pop() {
+            let storage_name = Storage::storage_name(&dependency);
+            let (index, first_visit) =
+                match resolved.iter().position(|(name, _)| *name == storage_name) {
+                    Some(index) => (index, false),
+                    None => {
+                        let dep_manifest = Storage::download(dependency.clone(), registry)?;
+                        if !dep_manifest.is_library() {
+                            return Err(format!(
+                                "Dependency {} is not a library package",
+                                dep_manifest.full_name()
+                            ));
+                        }
+                        lockfile.check(&dependency, &dep_manifest)?;
+                        sources.push((dependency.clone(), dep_manifest.clone()));
+                        resolved.push((storage_name, dep_manifest));
+                        (resolved.len() - 1, true)
+                    }
+                };
+
+            let dep_manifest = &mut resolved[index].1;
+            let requested = dep_manifest.features().enable(&request).map_err(|e| {
+                format!(
+                    "{} requests a feature of {}: {}",
+                    dependent,
+                    dep_manifest.full_name(),
+                    e
+                )
+            })?;
+            let mut enabled = dep_manifest.enabled_features().to_vec();
+            let grown = requested.iter().any(|feature| !enabled.contains(feature));
+            if !first_visit && !grown {
                 continue;
             }
+            enabled.extend(requested);
+            enabled.sort();
+            enabled.dedup();
+            dep_manifest.set_enabled_features(enabled);
 
-            let dep_manifest = Storage::download(dependency.clone(), registry)?;
-            if !dep_manifest.is_library() {
-                return Err(format!(
-                    "Dependency {} is not a library package",
-                    dep_manifest.full_name()
-                ));
+            for dep in dep_manifest.dependencies() {
+                let request = dep_manifest.feature_request_for(&dep);
+                open_list.push((dep, dep_manifest.full_name(), request));
             }
+        }
 
-            closed_list.push(dependency.clone());
-            dependencies.push(dep_manifest.clone());
+        let dev_only = Self::dev_only(&manifest, &resolved);
+        let mut dependencies = Self::sort_dependencies(&manifest, &dev_dependencies, &resolved);
 
-            for dep in dep_manifest.dependencies() {
-                if !closed_list.contains(&dep) {
-                    open_list.push(dep);
-                }
-            }
+        manifest.set_includes(Self::resolve_includes(&manifest, &resolved));
+        manifest.set_dev_includes(Self::dependency_includes(dev_dependencies, &resolved));
+        for dep in dependencies.iter_mut() {
+            dep.set_includes(Self::resolve_includes(dep, &resolved));
         }
 
-        manifest.set_includes(Self::resolve_includes(&manifest)?);
+        Self::resolve_options(&manifest, &mut dependencies)?;
+
+        let mut probed = vec![];
+        let (cflags, libs) = Self::resolve_system_flags(&manifest, &resolved, &mut probed)?;
+        manifest.set_system_flags(cflags, libs);
         for dep in dependencies.iter_mut() {
-            dep.set_includes(Self::resolve_includes(dep)?);
+            let (cflags, libs) = Self::resolve_system_flags(dep, &resolved, &mut probed)?;
+            dep.set_system_flags(cflags, libs);
         }
 
         Ok(Self {
             manifest,
             dependencies,
+            sources,
+            dev_only,
+            dev,
         })
     }
 
-    fn resolve_includes(manifest: &Manifest) -> Result<Vec<PatternPath>, String> {
+    /// Full names of the resolved packages only reachable through dev-dependencies.
+    fn dev_only(root: &Manifest, resolved: &[(String, Manifest)]) -> Vec<String> {
+        let mut reachable: Vec<String> = vec![];
+        let mut open_list = root.dependencies();
+        while let Some(dependency) = open_list.pop() {
+            let storage_name = Storage::storage_name(&dependency);
+            if reachable.contains(&storage_name) {
+                continue;
+            }
+            if let Some((_, manifest)) = resolved.iter().find(|(name, _)| *name == storage_name) {
+                open_list.extend(manifest.dependencies());
+            }
+            reachable.push(storage_name);
+        }
+
+        resolved
+            .iter()
+            .filter(|(name, _)| !reachable.contains(name))
+            .map(|(_, manifest)| manifest.full_name())
+            .collect()
+    }
+
+    /// Orders resolved dependencies so that every package comes before the
+    /// packages it depends on, which is the order a linker expects archives in.
+    fn sort_dependencies(
+        root: &Manifest,
+        dev_dependencies: &[Dependency],
+        resolved: &[(String, Manifest)],
+    ) -> Vec<Manifest> {
+        fn visit(
+            storage_name: &str,
+            resolved: &[(String, Manifest)],
+            visited: &mut Vec<String>,
+            post_order: &mut Vec<usize>,
+        ) {
+            if visited.iter().any(|name| name == storage_name) {
+                return;
+            }
+            visited.push(storage_name.to_string());
+
+            let Some(index) = resolved.iter().position(|(name, _)| name == storage_name) else {
+                return;
+            };
+
+            for dependency in resolved[index].1.dependencies() {
+                visit(
+                    &Storage::storage_name(&dependency),
+                    resolved,
+                    visited,
+                    post_order,
+                );
+            }
+
+            post_order.push(index);
+        }
+
+        let mut visited = vec![];
+        let mut post_order = vec![];
+        for dependency in root.dependencies().iter().chain(dev_dependencies) {
+            visit(
+                &Storage::storage_name(dependency),
+                resolved,
+                &mut visited,
+                &mut post_order,
+            );
+        }
+
+        post_order
+            .into_iter()
+            .rev()
+            .map(|index| resolved[index].1.clone())
+            .collect()
+    }
+
+    /// Include directories of the package and of its direct dependencies.
+    ///
+    /// A header-only dependency's headers are compiled as part of the package,
+    /// so the include directories of its own dependencies are added as well.
+    fn resolve_includes(manifest: &Manifest, resolved: &[(String, Manifest)]) -> Vec<PatternPath> {
         let mut includes = manifest.includes().to_vec();
+        includes.extend(Self::dependency_includes(manifest.dependencies(), resolved));
 
-        for dependency in manifest.dependencies() {
-            let dep_manifest = Storage::download(dependency.clone(), &Registry::default())?;
+        includes
+    }
+
+    /// Include directories of `dependencies`, and of the dependencies of the
+    /// header-only ones, as resolved with their unified features.
+    fn dependency_includes(
+        dependencies: Vec<Dependency>,
+        resolved: &[(String, Manifest)],
+    ) -> Vec<PatternPath> {
+        let mut includes = vec![];
 
-            includes.extend(dep_manifest.includes().to_vec());
+        for dep_manifest in Self::resolved(&dependencies, resolved) {
+            match dep_manifest.is_header_only() {
+                true => includes.extend(Self::resolve_includes(dep_manifest, resolved)),
+                false => includes.extend(dep_manifest.includes().to_vec()),
+            }
         }
 
-        Ok(includes)
+        includes
     }
 
-    pub fn options(&self) -> Vec<KeyValue> {
-        self.manifest
-            .dependencies()
+    /// The resolved manifests of `dependencies`.
+    fn resolved<'a>(
+        dependencies: &[Dependency],
+        resolved: &'a [(String, Manifest)],
+    ) -> Vec<&'a Manifest> {
+        dependencies
             .iter()
-            .flat_map(|dep| dep.options().to_vec())
-            .collect::<Vec<_>>()
+            .filter_map(|dependency| {
+                let storage_name = Storage::storage_name(dependency);
+                resolved
+                    .iter()
+                    .find(|(name, _)| *name == storage_name)
+                    .map(|(_, manifest)| manifest)
+            })
+            .collect()
     }
 
+    /// Resolves the options of every dependency.
+    ///
+    /// A package starts from its own `[options]`, overridden by what its direct
+    /// dependents request in `[<name>.options]`. When two dependents request
+    /// different values (e.g. in a diamond), the build fails unless the root
+    /// package sets the option itself; the root's `[<name>.options]` apply to
+    /// any package of the graph and always win.
+    fn resolve_options(root: &Manifest, dependencies: &mut [Manifest]) -> Result<(), String> {
+        let packages = dependencies
+            .iter()
+            .chain(std::iter::once(root))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for dependency in dependencies.iter_mut() {
+            let mut requests: Vec<(String, KeyValue)> = vec![];
+            for dependent in &packages {
+                for requested in dependent.dependencies() {
+                    if requested.name() == dependency.name() {
+                        requests.extend(
+                            requested
+                                .options()
+                                .iter()
+                                .map(|option| (dependent.full_name(), option.clone())),
+                        );
+                    }
+                }
+            }
+
+            // Validate against the schema first, so that e.g. `1` and `1.0` agree.
+            let validate = |requester: &str, option: &KeyValue| match dependency.options_schema() {
+                Some(schema) => schema.validate(option).map_err(|e| {
+                    format!(
+                        "{} sets an invalid option for {}: {}",
+                        requester,
+                        dependency.full_name(),
+                        e
+                    )
+                }),
+                None => Ok(option.clone()),
+            };
+            let requests = requests
+                .iter()
+                .map(|(dependent, option)| Ok((dependent.clone(), validate(dependent, option)?)))
+                .collect::<Result<Vec<_>, String>>()?;
+            let overrides = root
+                .options_for(dependency.name())
+                .iter()
+                .map(|option| validate(&root.full_name(), option))
+                .collect::<Result<Vec<_>, String>>()?;
+            let mut options = dependency.options().to_vec();
+            let mut set = |option: &KeyValue| {
+                options.retain(|o| o.key != option.key);
+                options.push(option.clone());
+            };
+
+            for (dependent, option) in &requests {
+                if overrides.iter().any(|o| o.key == option.key) {
+                    continue;
+                }
+                if let Some((other, conflicting)) = requests
+                    .iter()
+                    .find(|(_, o)| o.key == option.key && o.value != option.value)
+                {
+                    return Err(format!(
+                        "conflicting values for option `{}` of {}: {} requests {} but {} requests {}; set it in the root package under [{}.options]",
+                        option.key,
+                        dependency.full_name(),
+                        dependent,
+                        option.value,
+                        other,
+                        conflicting.value,
+                        dependency.name()
+                    ));
+                }
+                set(option);
+            }
+            for option in &overrides {
+                set(option);
+            }
+
+            dependency.set_options(options);
+        }
+
+        Ok(())
+    }
+
+    /// Resolves `links` and `[system-dependencies]` of `manifest` into compile and link flags.
+    ///
+    /// Like include directories, the compile flags of a direct dependency's
+    /// system dependencies are visible to the package, since its public
+    /// headers may include theirs.
+    fn resolve_system_flags(
+        manifest: &Manifest,
+        resolved: &[(String, Manifest)],
+        probed: &mut Vec<(String, SystemLibrary)>,
+    ) -> Result<(Vec<String>, Vec<String>), String> {
+        let mut probe = |dependency: &SystemDependency| -> Result<SystemLibrary, String> {
+            let spec = dependency.module_spec();
+            if let Some((_, library)) = probed.iter().find(|(s, _)| *s == spec) {
+                return Ok(library.clone());
+            }
+
+            let library = PkgConfig::probe(dependency)
+                .map_err(|e| format!("{}: {}", manifest.full_name(), e))?;
+            probed.push((spec, library.clone()));
+            Ok(library)
+        };
+
+        let mut cflags = vec![];
+        let mut libs = vec![];
+        for dependency in manifest.system_dependencies() {
+            let library = probe(dependency)?;
+            cflags.extend(library.cflags);
+            libs.extend(library.libs);
+        }
+        libs.extend(manifest.links().iter().map(|link| format!("-l{}", link)));
+
+        for dep_manifest in Self::resolved(&manifest.dependencies(), resolved) {
+            for system_dependency in dep_manifest.system_dependencies() {
+                cflags.extend(probe(system_dependency)?.cflags);
+            }
+        }
+
+        Ok((cflags, libs))
+    }
+
+    /// Resolved dependencies, dependents first (link order).
     pub fn dependencies(&self) -> &[Manifest] {
         &self.dependencies
     }
 
+    /// Every resolved dependency with the package it resolved to, as recorded
+    /// in `Tailor.lock`.
+    pub fn sources(&self) -> &[(Dependency, Manifest)] {
+        &self.sources
+    }
+
+    /// Whether the root's `[dev-dependencies]` were resolved.
+    pub fn has_dev_dependencies(&self) -> bool {
+        self.dev
+    }
+
+    /// Whether `manifest` is only needed by the root's tests and examples, and
+    /// stays out of its binaries and library.
+    pub fn is_dev_only(&self, manifest: &Manifest) -> bool {
+        self.dev_only.contains(&manifest.full_name())
+    }
+
     pub fn manifest(&self) -> &Manifest {
         &self.manifest
     }
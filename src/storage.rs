@@ -4,13 +4,15 @@ use crate::{
     absolute_path::AbsolutePath,
     external_tool::{checksum::Checksum, git::Git, registry::Registry},
     manifest::{Manifest, dependency::Dependency},
+    workspace::Workspace,
 };
 use dirs::home_dir;
 
 pub struct Storage;
 
 impl Storage {
-    fn storage_dir() -> Result<AbsolutePath, String> {
+    /// `~/.tailor/packages`, where dependencies are downloaded.
+    pub fn storage_dir() -> Result<AbsolutePath, String> {
         home_dir()
             .ok_or_else(|| "Failed to get home directory".to_string())?
             .join(".tailor")
@@ -54,6 +56,13 @@ impl Storage {
         expected_checksum == actual_checksum.0
     }
 
+    /// The checksum saved for the package stored in `path`, if it was downloaded.
+    pub fn checksum(path: &AbsolutePath) -> Option<String> {
+        std::fs::read_to_string(path.inner().join("Tailor.sha256"))
+            .ok()
+            .map(|checksum| checksum.trim().to_string())
+    }
+
     fn save_checksum(path: &AbsolutePath) -> Result<(), String> {
         let checksum = Checksum::from_directory(path)?;
         let checksum_hex = hex::encode(checksum.0);
@@ -71,7 +80,15 @@ impl Storage {
         Ok(manifest)
     }
 
+    /// The manifest of `dependency`, fetched into storage unless it is a
+    /// member of a workspace, which is used in place.
     pub fn download(dependency: Dependency, registry: &Registry) -> Result<Manifest, String> {
+        if let Dependency::Local { path, .. } = &dependency
+            && Workspace::find(path)?.is_some_and(|workspace| workspace.is_member(path.inner()))
+        {
+            return Self::load_manifest(path);
+        }
+
         let storage_name = Self::storage_name_path(&dependency)?;
         let exists = std::fs::metadata(storage_name.inner()).is_ok();
         let integrity = Self::integrity(&storage_name);
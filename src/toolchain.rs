@@ -0,0 +1,170 @@
+use crate::absolute_path::AbsolutePath;
+use dirs::home_dir;
+use std::path::PathBuf;
+
+/// Cross-compilation toolchain selected with `--target <name>`.
+///
+/// Loaded from `toolchains/<name>.toml` in the package directory or, if not
+/// found there, from `~/.tailor/toolchains/<name>.toml`:
+///
+/// ```toml
+/// cc = "arm-none-eabi-gcc"
+/// ar = "arm-none-eabi-ar"
+/// objcopy = "arm-none-eabi-objcopy"
+/// sysroot = "/opt/arm-none-eabi"
+/// cflags = ["-mcpu=cortex-m4", "-mthumb"]
+/// ldflags = ["-specs=nosys.specs"]
+/// runner = ["qemu-arm"]
+/// ```
+#[derive(Clone)]
+pub struct Toolchain {
+    name: String,
+    cc: String,
+    ar: String,
+    objcopy: Option<String>,
+    sysroot: Option<String>,
+    cflags: Vec<String>,
+    ldflags: Vec<String>,
+    runner: Vec<String>,
+}
+
+impl Toolchain {
+    fn search_paths(name: &str, project: &AbsolutePath) -> Vec<PathBuf> {
+        let file_name = format!("{}.toml", name);
+        let mut paths = vec![project.inner().join("toolchains").join(&file_name)];
+        if let Some(home) = home_dir() {
+            paths.push(home.join(".tailor").join("toolchains").join(&file_name));
+        }
+        paths
+    }
+
+    pub fn load(name: &str, project: &AbsolutePath) -> Result<Self, String> {
+        Self::check_name(name)?;
+
+        let paths = Self::search_paths(name, project);
+        let Some(path) = paths.iter().find(|path| path.exists()) else {
+            return Err(format!(
+                "toolchain for target `{}` not found; looked in {}",
+                name,
+                paths
+                    .iter()
+                    .map(|path| format!("`{}`", path.display()))
+                    .collect::<Vec<_>>()
+                    .join(" and ")
+            ));
+        };
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("fail to read toolchain {}: {}", path.display(), e))?;
+        Self::from_file(name, &content)
+            .map_err(|e| format!("invalid toolchain {}: {}", path.display(), e))
+    }
+
+    fn from_file(name: &str, content: &str) -> Result<Self, String> {
+        let table = content
+            .parse::<toml::Table>()
+            .map_err(|_| "not a valid TOML table".to_string())?;
+
+        let string = |key: &str| -> Result<Option<String>, String> {
+            table
+                .get(key)
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or(format!("'{}' must be a string", key))
+                })
+                .transpose()
+        };
+        let list = |key: &str| -> Result<Vec<String>, String> {
+            let Some(value) = table.get(key) else {
+                return Ok(vec![]);
+            };
+            value
+                .as_array()
+                .and_then(|items| {
+                    items
+                        .iter()
+                        .map(|item| item.as_str().map(String::from))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or(format!("'{}' must be a list of strings", key))
+        };
+
+        for key in table.keys() {
+            if ![
+                "cc", "ar", "objcopy", "sysroot", "cflags", "ldflags", "runner",
+            ]
+            .contains(&key.as_str())
+            {
+                return Err(format!("unknown key '{}'", key));
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            cc: string("cc")?.ok_or("missing 'cc'".to_string())?,
+            ar: string("ar")?.unwrap_or_else(|| "ar".to_string()),
+            objcopy: string("objcopy")?,
+            sysroot: string("sysroot")?,
+            cflags: list("cflags")?,
+            ldflags: list("ldflags")?,
+            runner: list("runner")?,
+        })
+    }
+
+    /// Target names are file names: letters, digits, `-`, `_` and `.`, not
+    /// starting with a `.` (so never `.` or `..`).
+    pub fn check_name(name: &str) -> Result<(), String> {
+        if name.is_empty()
+            || name.starts_with('.')
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        {
+            return Err(format!("invalid target name `{}`", name));
+        }
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cc(&self) -> &str {
+        &self.cc
+    }
+
+    pub fn ar(&self) -> &str {
+        &self.ar
+    }
+
+    pub fn objcopy(&self) -> Option<&str> {
+        self.objcopy.as_deref()
+    }
+
+    /// Command prefix used by `tailor run` to execute target binaries (e.g. an emulator).
+    pub fn runner(&self) -> &[String] {
+        &self.runner
+    }
+
+    /// Flags added to every compile command, before the profile's.
+    pub fn compile_flags(&self) -> Vec<String> {
+        let mut flags = vec![];
+        if let Some(sysroot) = &self.sysroot {
+            flags.push(format!("--sysroot={}", sysroot));
+        }
+        flags.extend(self.cflags.iter().cloned());
+        flags
+    }
+
+    /// Flags added to every link command, before the profile's.
+    pub fn link_flags(&self) -> Vec<String> {
+        let mut flags = vec![];
+        if let Some(sysroot) = &self.sysroot {
+            flags.push(format!("--sysroot={}", sysroot));
+        }
+        flags.extend(self.cflags.iter().cloned());
+        flags.extend(self.ldflags.iter().cloned());
+        flags
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{absolute_path::AbsolutePath, manifest::Manifest};
+use std::path::Path;
+
+/// Packages developed together, declared by a `Tailor.toml` with a
+/// `[workspace]` table:
+///
+/// ```toml
+/// [workspace]
+/// members = ["packages/*", "tools/cli"]
+/// ```
+///
+/// Members build in place into the workspace's `build/` directory and share
+/// its `Tailor.lock`. The workspace manifest may also describe a package,
+/// which is then a member as well.
+pub struct Workspace {
+    root: AbsolutePath,
+    members: Vec<AbsolutePath>,
+}
+
+impl Workspace {
+    /// The workspace `path` belongs to: the nearest one, from `path` upwards,
+    /// having `path` as its root or as one of its members.
+    pub fn find(path: &AbsolutePath) -> Result<Option<Self>, String> {
+        for directory in path.inner().ancestors() {
+            let Ok(content) = std::fs::read_to_string(directory.join("Tailor.toml")) else {
+                continue;
+            };
+            let Some(workspace) = Self::parse(&content, directory)? else {
+                continue;
+            };
+            if workspace.root.inner() == path.inner() || workspace.is_member(path.inner()) {
+                return Ok(Some(workspace));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn parse(content: &str, directory: &Path) -> Result<Option<Self>, String> {
+        let Ok(toml_table) = content.parse::<toml::Table>() else {
+            return Ok(None);
+        };
+        let Some(workspace) = toml_table.get("workspace") else {
+            return Ok(None);
+        };
+        let root: AbsolutePath = directory.try_into()?;
+        let fail = |e: String| {
+            format!(
+                "Failed to parse workspace {}: {}",
+                root.inner().display(),
+                e
+            )
+        };
+
+        let patterns = workspace
+            .as_table()
+            .and_then(|workspace| workspace.get("members"))
+            .and_then(|members| members.as_array())
+            .and_then(|members| {
+                members
+                    .iter()
+                    .map(|member| member.as_str())
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or(fail("'members' must be a list of paths".to_string()))?;
+
+        let mut members = vec![];
+        if toml_table.contains_key("name") {
+            members.push(root.clone());
+        }
+        for pattern in patterns {
+            let full_pattern = root.inner().join(pattern).to_string_lossy().to_string();
+            let paths = glob::glob(&full_pattern)
+                .map_err(|e| fail(format!("invalid member pattern `{}`: {}", pattern, e)))?
+                .filter_map(Result::ok)
+                .filter(|path| path.is_dir())
+                .collect::<Vec<_>>();
+            if paths.is_empty() {
+                return Err(fail(format!("member `{}` matches no directory", pattern)));
+            }
+            for path in paths {
+                if !path.join("Tailor.toml").is_file() {
+                    return Err(fail(format!(
+                        "member {} has no Tailor.toml",
+                        path.display()
+                    )));
+                }
+                let member: AbsolutePath = path.try_into()?;
+                if !members
+                    .iter()
+                    .any(|m: &AbsolutePath| m.inner() == member.inner())
+                {
+                    members.push(member);
+                }
+            }
+        }
+
+        Ok(Some(Self { root, members }))
+    }
+
+    /// Whether the package in `path` is a member of the workspace, and is
+    /// therefore used in place rather than copied into storage.
+    pub fn is_member(&self, path: &Path) -> bool {
+        let path = path.canonicalize().unwrap_or(path.to_path_buf());
+        self.members.iter().any(|member| member.inner() == path)
+    }
+
+    pub fn root(&self) -> &AbsolutePath {
+        &self.root
+    }
+
+    pub fn members(&self) -> &[AbsolutePath] {
+        &self.members
+    }
+
+    /// Members to work on from `path`: those named with `-p`, else the member
+    /// at `path`, else (from a workspace root without a package) every member.
+    pub fn select(
+        &self,
+        path: &AbsolutePath,
+        packages: &[String],
+    ) -> Result<Vec<AbsolutePath>, String> {
+        if packages.is_empty() {
+            return Ok(match self.is_member(path.inner()) {
+                true => vec![path.clone()],
+                false => self.members.clone(),
+            });
+        }
+
+        let manifests = self
+            .members
+            .iter()
+            .map(|member| Ok((member, Self::load(member)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        packages
+            .iter()
+            .map(|name| {
+                manifests
+                    .iter()
+                    .find(|(_, manifest)| manifest.name() == name)
+                    .map(|(member, _)| (*member).clone())
+                    .ok_or(format!(
+                        "package `{}` is not a member of the workspace; members are {}",
+                        name,
+                        manifests
+                            .iter()
+                            .map(|(_, manifest)| format!("`{}`", manifest.name()))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+            })
+            .collect()
+    }
+
+    pub fn load(member: &AbsolutePath) -> Result<Manifest, String> {
+        let content = std::fs::read_to_string(member.inner().join("Tailor.toml"))
+            .map_err(|_| format!("fail to read {}/Tailor.toml", member.inner().display()))?;
+        Manifest::from_file(&content, member)
+    }
+}
@@ -0,0 +1,8 @@
+#ifndef $pkg_name_guard
+#define $pkg_name_guard
+
+#include <stdio.h>
+
+static inline void $pkg_name() { printf("Hello from the $pkg_name library!\n"); }
+
+#endif /* $pkg_name_guard */
@@ -0,0 +1,83 @@
+/* Generated by tailor; do not edit.
+ *
+ * A minimal harness for `tailor test`: each case reports a line that tailor
+ * records in its JUnit and TAP reports.
+ *
+ *   #include <tailor_test.h>
+ *
+ *   TAILOR_TEST(adds) { TAILOR_ASSERT_EQ(5, add(2, 3)); }
+ *
+ *   int main(void) {
+ *     TAILOR_RUN(adds);
+ *     return TAILOR_RESULT();
+ *   }
+ */
+#ifndef TAILOR_TEST_H
+#define TAILOR_TEST_H
+
+#include <stdio.h>
+#include <time.h>
+
+enum { TAILOR_TEST_PASSED, TAILOR_TEST_FAILED, TAILOR_TEST_SKIPPED };
+
+static int tailor_test_status_;
+static int tailor_test_failures_;
+static char tailor_test_message_[512];
+
+#define TAILOR_TEST(name) static void name(void)
+
+#define TAILOR_FAIL(message)                                                        \
+  do {                                                                              \
+    snprintf(tailor_test_message_, sizeof tailor_test_message_, "%s:%d: %s",        \
+             __FILE__, __LINE__, (message));                                        \
+    tailor_test_status_ = TAILOR_TEST_FAILED;                                       \
+    return;                                                                         \
+  } while (0)
+
+#define TAILOR_ASSERT(condition)                                                    \
+  do {                                                                              \
+    if (!(condition)) TAILOR_FAIL("assertion failed: " #condition);                 \
+  } while (0)
+
+#define TAILOR_ASSERT_EQ(expected, actual)                                          \
+  do {                                                                              \
+    long long tailor_expected_ = (long long)(expected);                             \
+    long long tailor_actual_ = (long long)(actual);                                 \
+    if (tailor_expected_ != tailor_actual_) {                                       \
+      snprintf(tailor_test_message_, sizeof tailor_test_message_,                   \
+               "%s:%d: expected %s == %lld, got %lld", __FILE__, __LINE__, #actual, \
+               tailor_expected_, tailor_actual_);                                   \
+      tailor_test_status_ = TAILOR_TEST_FAILED;                                     \
+      return;                                                                       \
+    }                                                                               \
+  } while (0)
+
+#define TAILOR_SKIP(reason)                                                         \
+  do {                                                                              \
+    snprintf(tailor_test_message_, sizeof tailor_test_message_, "%s", (reason));    \
+    tailor_test_status_ = TAILOR_TEST_SKIPPED;                                      \
+    return;                                                                         \
+  } while (0)
+
+#define TAILOR_RUN(test) tailor_test_run_(#test, test)
+
+/* Exit status of the test program: nonzero when a case failed. */
+#define TAILOR_RESULT() (tailor_test_failures_ != 0)
+
+static inline void tailor_test_run_(const char *name, void (*test)(void)) {
+  static const char *statuses[] = {"ok", "failed", "skipped"};
+  clock_t start = clock();
+  double seconds;
+
+  tailor_test_status_ = TAILOR_TEST_PASSED;
+  tailor_test_message_[0] = '\0';
+  test();
+  seconds = (double)(clock() - start) / CLOCKS_PER_SEC;
+
+  if (tailor_test_status_ == TAILOR_TEST_FAILED) tailor_test_failures_++;
+  printf("tailor-test: %s %s %.6f%s%s\n", statuses[tailor_test_status_], name, seconds,
+         tailor_test_message_[0] ? " " : "", tailor_test_message_);
+  fflush(stdout);
+}
+
+#endif /* TAILOR_TEST_H */
@@ -0,0 +1,2096 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use common::{assert_success, find_files, setup_test_dir, tailor, write_file};
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .expect("Failed to read modification time")
+}
+
+#[test]
+fn test_build_is_incremental() {
+    let test_dir = setup_test_dir("incremental");
+    let test_path = &test_dir.path;
+
+    assert_success(&tailor(["new".as_ref(), test_path.as_os_str()]));
+    write_file(&test_path.join("include/answer.h"), "#define ANSWER 42\n");
+    write_file(
+        &test_path.join("src/main.c"),
+        "#include \"answer.h\"\n\nint main() { return ANSWER - 42; }\n",
+    );
+
+    // First build compiles everything
+    let output = tailor(["build".as_ref(), test_path.as_os_str()]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Compiling"), "First build should compile");
+
+    let object = find_files(&test_path.join("build/debug"), "o")
+        .into_iter()
+        .next()
+        .expect("Object file was not created");
+    let first_build = modified(&object);
+
+    // No-op build compiles nothing
+    let output = tailor(["build".as_ref(), test_path.as_os_str()]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("Compiling"),
+        "No-op build should not compile. Got stdout: {}",
+        stdout
+    );
+    assert_eq!(
+        first_build,
+        modified(&object),
+        "Object should not be rebuilt"
+    );
+
+    // Touching an included header triggers a rebuild
+    write_file(&test_path.join("include/answer.h"), "#define ANSWER 42 \n");
+    let output = tailor(["build".as_ref(), test_path.as_os_str()]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Compiling"),
+        "Header change should trigger a rebuild"
+    );
+    assert_ne!(first_build, modified(&object), "Object should be rebuilt");
+}
+
+#[test]
+fn test_build_with_parallel_jobs() {
+    let test_dir = setup_test_dir("parallel_jobs");
+    let test_path = &test_dir.path;
+
+    assert_success(&tailor(["new".as_ref(), test_path.as_os_str()]));
+    let mut main_c = String::new();
+    for i in 0..8 {
+        write_file(
+            &test_path.join(format!("src/part{i}.c")),
+            &format!("int part{i}(void) {{ return {i}; }}\n"),
+        );
+        main_c.push_str(&format!("int part{i}(void);\n"));
+    }
+    main_c.push_str("\nint main() { return part0() + part7() - 7; }\n");
+    write_file(&test_path.join("src/main.c"), &main_c);
+
+    let output = tailor([
+        "run".as_ref(),
+        "--jobs".as_ref(),
+        "4".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert_success(&output);
+    assert_eq!(find_files(&test_path.join("build/debug"), "o").len(), 9);
+}
+
+#[test]
+fn test_build_rejects_invalid_jobs() {
+    let test_dir = setup_test_dir("invalid_jobs");
+    let test_path = &test_dir.path;
+
+    assert_success(&tailor(["new".as_ref(), test_path.as_os_str()]));
+
+    let output = tailor([
+        "build".as_ref(),
+        "-j".as_ref(),
+        "0".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Usage"),
+        "Invalid --jobs should print the usage. Got stdout: {}",
+        stdout
+    );
+    assert!(
+        !test_path.join("build").exists(),
+        "Nothing should be built when --jobs is zero"
+    );
+}
+
+#[test]
+fn test_build_objects_with_same_stem() {
+    let test_dir = setup_test_dir("same_stem");
+    let test_path = &test_dir.path;
+    let dep_path = test_path.join("same_stem_dep");
+    let app_path = test_path.join("same_stem_app");
+
+    write_file(
+        &dep_path.join("Tailor.toml"),
+        "name = \"same_stem_dep\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+    );
+    write_file(
+        &dep_path.join("src/util.c"),
+        "int dep_util(void) { return 1; }\n",
+    );
+
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"same_stem_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\nsources = [\"src/*.c\", \"src/a/*.c\", \"src/b/*.c\"]\n\n[dependencies]\nsame_stem_dep = { path = \"../same_stem_dep\" }\n",
+    );
+    write_file(
+        &app_path.join("src/util.c"),
+        "int app_util(void) { return 2; }\n",
+    );
+    write_file(&app_path.join("src/a/x.c"), "int a_x(void) { return 3; }\n");
+    write_file(&app_path.join("src/b/x.c"), "int b_x(void) { return 4; }\n");
+    write_file(
+        &app_path.join("src/main.c"),
+        "int dep_util(void);\nint app_util(void);\nint a_x(void);\nint b_x(void);\n\nint main() { return dep_util() + app_util() + a_x() + b_x() - 10; }\n",
+    );
+
+    let output = tailor(["run".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+
+    let obj_path = app_path.join("build/debug/obj");
+    assert!(obj_path.join("same_stem_app@0.1.0/src/util.o").exists());
+    assert!(obj_path.join("same_stem_app@0.1.0/src/a/x.o").exists());
+    assert!(obj_path.join("same_stem_app@0.1.0/src/b/x.o").exists());
+    assert!(obj_path.join("same_stem_dep@0.1.0/src/util.o").exists());
+}
+
+#[test]
+fn test_build_links_dependencies_as_archives() {
+    let test_dir = setup_test_dir("archives");
+    let test_path = &test_dir.path;
+    let low_path = test_path.join("archives_low");
+    let high_path = test_path.join("archives_high");
+    let app_path = test_path.join("archives_app");
+
+    // archives_app -> archives_high -> archives_low
+    write_file(
+        &low_path.join("Tailor.toml"),
+        "name = \"archives_low\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+    );
+    write_file(&low_path.join("src/low.c"), "int low(void) { return 1; }\n");
+    // Transitive path dependencies are resolved from the storage copy, so use an absolute path
+    write_file(
+        &high_path.join("Tailor.toml"),
+        &format!(
+            "name = \"archives_high\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\nlib-kind = \"both\"\n\n[dependencies]\narchives_low = {{ path = \"{}\" }}\n",
+            low_path.display()
+        ),
+    );
+    write_file(
+        &high_path.join("src/high.c"),
+        "int low(void);\n\nint high(void) { return low() + 1; }\n",
+    );
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"archives_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\narchives_high = { path = \"../archives_high\" }\n",
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "int high(void);\n\nint main() { return high() - 2; }\n",
+    );
+
+    let output = tailor(["run".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+
+    let build_path = app_path.join("build/debug");
+    assert!(
+        build_path
+            .join("deps/archives_high@0.1.0/libarchives_high.a")
+            .exists()
+    );
+    assert!(
+        build_path
+            .join("deps/archives_low@0.1.0/libarchives_low.a")
+            .exists()
+    );
+
+    // A library root package builds its own archive, and a shared object on request
+    let output = tailor(["build".as_ref(), high_path.as_os_str()]);
+    assert_success(&output);
+    assert!(high_path.join("build/debug/libarchives_high.a").exists());
+    assert!(high_path.join("build/debug/libarchives_high.so").exists());
+}
+
+#[test]
+fn test_build_path_and_options_with_shell_metacharacters() {
+    let test_dir = setup_test_dir("shell meta's $dir");
+    let test_path = &test_dir.path;
+    let dep_path = test_path.join("meta_dep");
+    let app_path = test_path.join("meta app");
+
+    write_file(
+        &dep_path.join("Tailor.toml"),
+        "name = \"meta_dep\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+    );
+    // Options only reach the sources of the package they are set for
+    write_file(
+        &dep_path.join("src/meta.c"),
+        "#include <string.h>\n\nint meta(void) { return strcmp(GREETING, \"say \\\"hi\\\"; $(exit 1) `false` \\\\n\") != 0; }\n",
+    );
+
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"meta_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\nmeta_dep = { path = \"../meta_dep\" }\n\n[meta_dep.options]\ngreeting = 'say \"hi\"; $(exit 1) `false` \\n'\n",
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "int meta(void);\n\n#ifdef GREETING\n#error dependency options leaked into the package\n#endif\n\nint main() { return meta(); }\n",
+    );
+
+    let output = tailor(["run".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+}
+
+#[test]
+fn test_build_custom_profile() {
+    let test_dir = setup_test_dir("custom_profile");
+    let test_path = &test_dir.path;
+
+    assert_success(&tailor(["new".as_ref(), test_path.as_os_str()]));
+    write_file(
+        &test_path.join("Tailor.toml"),
+        "name = \"custom_profile\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[profile.bench]\ninherits = \"release\"\nopt-level = 2\ncflags = [\"-fno-common\"]\n\n[profile.bench.defines]\nBENCH = 1\n",
+    );
+    write_file(
+        &test_path.join("src/main.c"),
+        "int main() { return BENCH - 1; }\n",
+    );
+
+    let output = tailor([
+        "run".as_ref(),
+        "--profile".as_ref(),
+        "bench".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert_success(&output);
+    assert!(test_path.join("build/bench/custom_profile@0.1.0").exists());
+
+    // The define only exists in the bench profile
+    let output = tailor(["build".as_ref(), test_path.as_os_str()]);
+    assert!(!output.status.success(), "debug build should not see BENCH");
+
+    // Cleaning a profile leaves the other profiles alone
+    assert_success(&tailor([
+        "clean".as_ref(),
+        "--profile".as_ref(),
+        "bench".as_ref(),
+        test_path.as_os_str(),
+    ]));
+    assert!(!test_path.join("build/bench").exists());
+    assert!(test_path.join("build/debug").exists());
+
+    // ... and takes the profile's sanitized and coverage builds with it
+    for variant in ["bench+address", "bench+coverage", "benchmark"] {
+        write_file(&test_path.join("build").join(variant).join("artifact"), "");
+    }
+    assert_success(&tailor([
+        "clean".as_ref(),
+        "--profile=bench".as_ref(),
+        test_path.as_os_str(),
+    ]));
+    assert!(!test_path.join("build/bench+address").exists());
+    assert!(!test_path.join("build/bench+coverage").exists());
+    assert!(test_path.join("build/benchmark").exists());
+
+    // Undefined profiles are rejected
+    let output = tailor([
+        "build".as_ref(),
+        "--profile".as_ref(),
+        "missing".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("profile `missing` is not defined"),
+        "Got stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_build_package_flags() {
+    let test_dir = setup_test_dir("package_flags");
+    let test_path = &test_dir.path;
+    let dep_path = test_path.join("flags_dep");
+    let app_path = test_path.join("flags_app");
+    let link_map = test_path.join("link.map");
+
+    write_file(
+        &dep_path.join("Tailor.toml"),
+        &format!(
+            "name = \"flags_dep\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\nstd = \"c99\"\ncflags = [\"-DDEP_ONLY\"]\nldflags = [\"-Wl,-Map,{}\"]\n",
+            link_map.display()
+        ),
+    );
+    write_file(
+        &dep_path.join("src/dep.c"),
+        "#if !defined(DEP_ONLY) || defined(APP_ONLY) || __STDC_VERSION__ != 199901L\n#error wrong flags\n#endif\n\nint dep(void) { return 0; }\n",
+    );
+
+    write_file(
+        &app_path.join("Tailor.toml"),
+        &format!(
+            "name = \"flags_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\nstd = \"c11\"\ncflags = [\"-DAPP_ONLY\"]\n\n[dependencies]\nflags_dep = {{ path = \"{}\" }}\n",
+            dep_path.display()
+        ),
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "#if !defined(APP_ONLY) || defined(DEP_ONLY) || __STDC_VERSION__ != 201112L\n#error wrong flags\n#endif\n\nint dep(void);\n\nint main() { return dep(); }\n",
+    );
+
+    let output = tailor(["run".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    assert!(
+        link_map.exists(),
+        "Dependency ldflags should reach the final link"
+    );
+}
+
+#[test]
+fn test_build_system_libraries() {
+    let test_dir = setup_test_dir("system_libraries");
+    let test_path = &test_dir.path;
+    let dep_path = test_path.join("syslib_dep");
+    let app_path = test_path.join("syslib_app");
+
+    // The dependency needs zlib (through pkg-config) and libm; the app links them transitively
+    write_file(
+        &dep_path.join("Tailor.toml"),
+        "name = \"syslib_dep\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\nlinks = [\"m\"]\n\n[system-dependencies]\nzlib = \">= 1.2\"\n",
+    );
+    write_file(
+        &dep_path.join("src/dep.c"),
+        "#include <math.h>\n#include <zlib.h>\n\nint dep(double x) { return zlibVersion()[0] != '1' || (int)sqrt(x) != 3; }\n",
+    );
+
+    write_file(
+        &app_path.join("Tailor.toml"),
+        &format!(
+            "name = \"syslib_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\nsyslib_dep = {{ path = \"{}\" }}\n",
+            dep_path.display()
+        ),
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "int dep(double x);\n\nint main(int argc, char **argv) { (void)argv; return dep(8.0 + argc); }\n",
+    );
+
+    let output = tailor(["run".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+
+    // Unsatisfiable version requirements are reported
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"syslib_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[system-dependencies]\nzlib = \">= 999\"\n",
+    );
+    let output = tailor(["build".as_ref(), app_path.as_os_str()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("system dependency `zlib >= 999` not found"),
+        "Got stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_build_for_target() {
+    let test_dir = setup_test_dir("cross_target");
+    let test_path = &test_dir.path;
+
+    assert_success(&tailor(["new".as_ref(), test_path.as_os_str()]));
+    write_file(
+        &test_path.join("toolchains/host-cross.toml"),
+        "cc = \"gcc\"\nar = \"ar\"\nobjcopy = \"objcopy\"\ncflags = [\"-DTARGET_HOST\"]\nrunner = [\"env\"]\n",
+    );
+    write_file(
+        &test_path.join("src/main.c"),
+        "int main() {\n#ifdef TARGET_HOST\n  return 0;\n#else\n  return 1;\n#endif\n}\n",
+    );
+
+    let output = tailor([
+        "run".as_ref(),
+        "--target".as_ref(),
+        "host-cross".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert_success(&output);
+
+    let build_path = test_path.join("build/host-cross/debug");
+    assert!(build_path.join("cross_target@0.1.0").exists());
+    assert!(build_path.join("cross_target@0.1.0.bin").exists());
+    assert!(build_path.join("cross_target@0.1.0.hex").exists());
+    let compile_commands = fs::read_to_string(build_path.join("compile_commands.json"))
+        .expect("Failed to read compile_commands.json");
+    assert!(compile_commands.contains("\"target\": \"host-cross\""));
+
+    // Unknown targets are reported
+    let output = tailor([
+        "build".as_ref(),
+        "--target".as_ref(),
+        "nowhere".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("toolchain for target `nowhere` not found"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // A target is cleaned even once its toolchain file is gone
+    fs::remove_file(test_path.join("toolchains/host-cross.toml"))
+        .expect("Failed to remove the toolchain file");
+    assert_success(&tailor([
+        "clean".as_ref(),
+        "--target".as_ref(),
+        "host-cross".as_ref(),
+        test_path.as_os_str(),
+    ]));
+    assert!(!test_path.join("build/host-cross").exists());
+    let output = tailor([
+        "clean".as_ref(),
+        "--target=..".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+    assert!(test_path.join("src/main.c").exists());
+}
+
+#[test]
+fn test_build_compiler_family_settings() {
+    let test_dir = setup_test_dir("compiler_family");
+    let test_path = &test_dir.path;
+    let manifest = "name = \"compiler_family\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n";
+
+    assert_success(&tailor(["new".as_ref(), test_path.as_os_str()]));
+    write_file(
+        &test_path.join("src/main.c"),
+        "int main() { int unused; return 0; }\n",
+    );
+    write_file(
+        &test_path.join("Tailor.toml"),
+        &format!(
+            "{}\n[profile.strict]\ninherits = \"debug\"\nwarnings-as-errors = true\ncflags = [\"-Wall\"]\n",
+            manifest
+        ),
+    );
+
+    // The detected compiler is recorded with every command
+    assert_success(&tailor(["build".as_ref(), test_path.as_os_str()]));
+    let recorded = find_files(&test_path.join("build/debug/obj"), "cmd");
+    assert_eq!(recorded.len(), 1);
+    let command = fs::read_to_string(&recorded[0]).expect("Failed to read recorded command");
+    assert!(command.starts_with("gcc "), "Got command: {}", command);
+
+    // warnings-as-errors turns the unused variable into an error
+    let output = tailor([
+        "build".as_ref(),
+        "--profile".as_ref(),
+        "strict".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+
+    // Settings the compiler does not support are rejected before compiling
+    write_file(
+        &test_path.join("Tailor.toml"),
+        &format!(
+            "{}\n[profile.msan]\ninherits = \"debug\"\nsanitize = [\"memory\"]\n",
+            manifest
+        ),
+    );
+    let output = tailor([
+        "build".as_ref(),
+        "--profile".as_ref(),
+        "msan".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("profile `msan` uses the memory sanitizer, which gcc"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // Unknown compilers are reported
+    write_file(
+        &test_path.join("Tailor.toml"),
+        &format!("{}compiler = \"true\"\n", manifest),
+    );
+    let output = tailor(["build".as_ref(), test_path.as_os_str()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unrecognized compiler `true`"),
+        "Got stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_build_with_build_scripts() {
+    let test_dir = setup_test_dir("build_scripts");
+    let test_path = &test_dir.path;
+    let dep_path = test_path.join("scripted_dep");
+    let app_path = test_path.join("scripted_app");
+
+    // The dependency generates a public header with a shell script
+    write_file(
+        &dep_path.join("Tailor.toml"),
+        "name = \"scripted_dep\"\nversion = \"0.3.0\"\nedition = \"2026.1\"\ntype = \"lib\"\nbuild = \"gen.sh\"\n",
+    );
+    write_file(
+        &dep_path.join("gen.sh"),
+        "#!/bin/sh\nmkdir -p \"$TAILOR_OUT_DIR/include\"\necho \"#define SCRIPTED_DEP_VERSION \\\"$TAILOR_PKG_VERSION\\\"\" > \"$TAILOR_OUT_DIR/include/scripted_version.h\"\necho \"tailor:include=$TAILOR_OUT_DIR/include\"\n",
+    );
+    fs::set_permissions(
+        dep_path.join("gen.sh"),
+        std::os::unix::fs::PermissionsExt::from_mode(0o755),
+    )
+    .expect("Failed to make gen.sh executable");
+    write_file(&dep_path.join("src/dep.c"), "int dep(void) { return 0; }\n");
+
+    // The package generates a source from a table with a C build script
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"scripted_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\nbuild = \"build.c\"\n\n[dependencies]\nscripted_dep = { path = \"../scripted_dep\" }\n",
+    );
+    write_file(&app_path.join("table.txt"), "3\n");
+    write_file(
+        &app_path.join("build.c"),
+        r#"#include <stdio.h>
+#include <stdlib.h>
+
+int main(void) {
+    char path[4096];
+    int value = 0;
+    FILE *table = fopen("table.txt", "r");
+    if (!table || fscanf(table, "%d", &value) != 1) return 1;
+    fclose(table);
+
+    snprintf(path, sizeof path, "%s/table.c", getenv("TAILOR_OUT_DIR"));
+    FILE *out = fopen(path, "w");
+    if (!out) return 1;
+    fprintf(out, "int table_value(void) { return %d; }\n", value);
+    fclose(out);
+
+    printf("generating table for %s (%s)\n", getenv("TAILOR_PKG_NAME"), getenv("TAILOR_PROFILE"));
+    printf("tailor:source=%s\n", path);
+    printf("tailor:define=SCRIPTED_PROFILE=\"%s\"\n", getenv("TAILOR_PROFILE"));
+    printf("tailor:link=m\n");
+    printf("tailor:rerun-if-changed=table.txt\n");
+    return 0;
+}
+"#,
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        r#"#include <stdio.h>
+#include <scripted_version.h>
+
+int table_value(void);
+
+int main() {
+    printf("%s %s %d\n", SCRIPTED_DEP_VERSION, SCRIPTED_PROFILE, table_value());
+    return 0;
+}
+"#,
+    );
+
+    let output = tailor(["run".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("0.3.0 debug 3"), "Got stdout: {}", stdout);
+    assert_eq!(stdout.matches("(build script)").count(), 2);
+
+    // Nothing changed: the package's script does not run again
+    let output = tailor(["build".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("scripted_app v0.1.0 (build script)"),
+        "Got stdout: {}",
+        stdout
+    );
+
+    // A declared input changed: the script runs again
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    write_file(&app_path.join("table.txt"), "7\n");
+    let output = tailor(["run".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("scripted_app v0.1.0 (build script)"));
+    assert!(stdout.contains("0.3.0 debug 7"), "Got stdout: {}", stdout);
+
+    // Unknown directives are reported
+    write_file(
+        &app_path.join("build.c"),
+        "#include <stdio.h>\n\nint main(void) { printf(\"tailor:bogus=1\\n\"); return 0; }\n",
+    );
+    let output = tailor(["build".as_ref(), app_path.as_os_str()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unknown build script directive `tailor:bogus`"),
+        "Got stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_build_reports_diagnostics() {
+    let test_dir = setup_test_dir("diagnostics");
+    let test_path = &test_dir.path;
+
+    assert_success(&tailor(["new".as_ref(), test_path.as_os_str()]));
+    write_file(
+        &test_path.join("Tailor.toml"),
+        "name = \"diagnostics\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ncflags = [\"-Wall\"]\n",
+    );
+    write_file(
+        &test_path.join("src/main.c"),
+        "int main() {\n    int unused;\n    return missing;\n}\n",
+    );
+
+    // Diagnostics are summarized by package and file
+    let output = tailor(["build".as_ref(), test_path.as_os_str()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("diagnostics v0.1.0 generated 1 error and 1 warning"),
+        "Got stderr: {}",
+        stderr
+    );
+    assert!(stderr.contains("--> "), "Got stderr: {}", stderr);
+    assert!(stderr.contains("3:12 error: "), "Got stderr: {}", stderr);
+    assert!(stderr.contains("2:9 warning: "), "Got stderr: {}", stderr);
+
+    // Output that is not a diagnostic is printed along with them
+    write_file(
+        &test_path.join("Tailor.toml"),
+        "name = \"diagnostics\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ncflags = [\"-Wall\", \"-Wno-bogus\"]\n",
+    );
+    let output = tailor(["build".as_ref(), test_path.as_os_str()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("cc1: note: unrecognized command-line option '-Wno-bogus'"),
+        "Got stderr: {}",
+        stderr
+    );
+    assert!(stderr.contains("3:12 error: "), "Got stderr: {}", stderr);
+    assert!(!stderr.contains("In function"), "Got stderr: {}", stderr);
+    write_file(
+        &test_path.join("Tailor.toml"),
+        "name = \"diagnostics\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ncflags = [\"-Wall\"]\n",
+    );
+
+    // One JSON object per line for every diagnostic
+    let output = tailor([
+        "build".as_ref(),
+        "--message-format".as_ref(),
+        "json".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+    let messages = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("Invalid JSON line"))
+        .collect::<Vec<_>>();
+    let error = messages
+        .iter()
+        .find(|message| message["severity"] == "error")
+        .expect("Missing error message");
+    assert_eq!(error["reason"], "compiler-message");
+    assert_eq!(error["package"], "diagnostics@0.1.0");
+    assert_eq!(error["line"], 3);
+    assert_eq!(error["column"], 12);
+    assert!(error["file"].as_str().unwrap().ends_with("main.c"));
+    assert_eq!(messages.last().unwrap()["reason"], "build-finished");
+    assert_eq!(messages.last().unwrap()["success"], false);
+
+    // ... and for every artifact
+    write_file(&test_path.join("src/main.c"), "int main() { return 0; }\n");
+    let output = tailor([
+        "build".as_ref(),
+        "--message-format=json".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let kinds = stdout
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("Invalid JSON line"))
+        .filter(|message| message["reason"] == "compiler-artifact")
+        .map(|message| message["kind"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, ["object", "executable"]);
+}
+
+#[test]
+fn test_build_lints() {
+    let test_dir = setup_test_dir("lints");
+    let test_path = &test_dir.path;
+    let dep_path = test_path.join("noisy_dep");
+    let app_path = test_path.join("lints_app");
+    let manifest = "name = \"lints_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\nnoisy_dep = { path = \"../noisy_dep\" }\n";
+
+    // The dependency warns and even denies its own warnings
+    write_file(
+        &dep_path.join("Tailor.toml"),
+        "name = \"noisy_dep\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[lints]\nunused-variable = \"deny\"\n",
+    );
+    write_file(
+        &dep_path.join("src/noisy.c"),
+        "int noisy(void) { int unused; return 0; }\n",
+    );
+    write_file(&app_path.join("Tailor.toml"), manifest);
+    write_file(
+        &app_path.join("src/main.c"),
+        "int noisy(void);\n\nint main(int argc, char **argv) { (void)argc; return noisy(); }\n",
+    );
+
+    // The root package gets -Wall -Wextra; the dependency is silenced
+    let output = tailor(["build".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unused parameter"),
+        "Got stderr: {}",
+        stderr
+    );
+    assert!(!stderr.contains("noisy.c"), "Got stderr: {}", stderr);
+
+    // --verbose shows the dependency's warnings without failing on them
+    fs::remove_dir_all(app_path.join("build")).expect("Failed to remove build directory");
+    let output = tailor(["build".as_ref(), "--verbose".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("noisy_dep v0.1.0 generated 1 warning"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // Named warnings can be allowed or denied
+    write_file(
+        &app_path.join("Tailor.toml"),
+        &format!("{}\n[lints]\nunused-parameter = \"allow\"\n", manifest),
+    );
+    let output = tailor(["build".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !stderr.contains("unused parameter"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    write_file(
+        &app_path.join("Tailor.toml"),
+        &format!("{}\n[lints]\nunused-parameter = \"deny\"\n", manifest),
+    );
+    let output = tailor(["build".as_ref(), app_path.as_os_str()]);
+    assert!(!output.status.success());
+
+    // Named warnings stay warnings in a denied group, and `=` names are valid in deny
+    write_file(
+        &app_path.join("Tailor.toml"),
+        &format!(
+            "{}\n[lints]\nall = \"deny\"\nextra = \"deny\"\nunused-parameter = \"warn\"\n\"format=2\" = \"deny\"\n",
+            manifest
+        ),
+    );
+    let output = tailor(["build".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("generated 1 warning"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // Unknown levels are rejected
+    write_file(
+        &app_path.join("Tailor.toml"),
+        &format!("{}\n[lints]\nshadow = \"forbid\"\n", manifest),
+    );
+    let output = tailor(["build".as_ref(), app_path.as_os_str()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unknown lint level `forbid`"),
+        "Got stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_run_with_sanitizers() {
+    let test_dir = setup_test_dir("sanitize");
+    let test_path = &test_dir.path;
+
+    assert_success(&tailor(["new".as_ref(), test_path.as_os_str()]));
+    write_file(
+        &test_path.join("src/main.c"),
+        "#include <limits.h>\n#include <stdio.h>\n\nint main(int argc, char **argv) {\n    (void)argv;\n    int value = INT_MAX;\n    value += argc;\n    printf(\"%d\\n\", value);\n    return 0;\n}\n",
+    );
+
+    // Without sanitizers the overflow goes unnoticed
+    assert_success(&tailor(["run".as_ref(), test_path.as_os_str()]));
+
+    // With them, the first report stops the program
+    let output = tailor([
+        "run".as_ref(),
+        "--sanitize".as_ref(),
+        "undefined,address".as_ref(),
+        test_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("runtime error: signed integer overflow"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // Sanitized objects live in their own directory, whatever the order of the list
+    assert!(test_path.join("build/debug/sanitize@0.1.0").exists());
+    assert!(
+        test_path
+            .join("build/debug+address+undefined/sanitize@0.1.0")
+            .exists()
+    );
+    assert_success(&tailor([
+        "build".as_ref(),
+        "--sanitize=address,undefined".as_ref(),
+        test_path.as_os_str(),
+    ]));
+    assert_eq!(fs::read_dir(test_path.join("build")).unwrap().count(), 2);
+
+    // Unknown sanitizers are reported, whichever command is given them
+    for command in ["build", "run", "test"] {
+        let output = tailor([
+            command.as_ref(),
+            "--sanitize".as_ref(),
+            "address,bogus".as_ref(),
+            test_path.as_os_str(),
+        ]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains(
+                "unknown sanitizer `bogus`; expected address, undefined, thread, leak or memory"
+            ),
+            "Got stderr: {}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn test_build_scoped_options() {
+    let test_dir = setup_test_dir("scoped_options");
+    let test_path = &test_dir.path;
+    let base_path = test_path.join("diamond_base");
+    let left_path = test_path.join("diamond_left");
+    let right_path = test_path.join("diamond_right");
+    let app_path = test_path.join("diamond_app");
+
+    // diamond_app -> diamond_left, diamond_right -> diamond_base
+    write_file(
+        &base_path.join("Tailor.toml"),
+        "name = \"diamond_base\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[options]\nlevel = 1\n",
+    );
+    write_file(
+        &base_path.join("src/base.c"),
+        "int base(void) { return LEVEL; }\n",
+    );
+    for (path, name, level) in [
+        (&left_path, "diamond_left", 2),
+        (&right_path, "diamond_right", 3),
+    ] {
+        write_file(
+            &path.join("Tailor.toml"),
+            &format!(
+                "name = \"{}\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[dependencies]\ndiamond_base = {{ path = \"{}\" }}\n\n[diamond_base.options]\nlevel = {}\n",
+                name,
+                base_path.display(),
+                level
+            ),
+        );
+        write_file(
+            &path.join(format!("src/{}.c", name)),
+            &format!("int {}(void) {{ return 0; }}\n", name),
+        );
+    }
+    let manifest = "name = \"diamond_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[options]\nexpected = 4\n\n[dependencies]\ndiamond_left = { path = \"../diamond_left\" }\ndiamond_right = { path = \"../diamond_right\" }\n";
+    write_file(
+        &app_path.join("src/main.c"),
+        "int base(void);\nint diamond_left(void);\nint diamond_right(void);\n\n#ifdef LEVEL\n#error dependency options leaked into the package\n#endif\n\nint main() { return diamond_left() + diamond_right() + (base() != EXPECTED); }\n",
+    );
+
+    // The dependents disagree on `level`
+    write_file(&app_path.join("Tailor.toml"), manifest);
+    let output = tailor(["build".as_ref(), app_path.as_os_str()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("conflicting values for option `level` of diamond_base@0.1.0"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // The root package settles it, even for an indirect dependency
+    write_file(
+        &app_path.join("Tailor.toml"),
+        &format!("{}\n[diamond_base.options]\nlevel = 4\n", manifest),
+    );
+    assert_success(&tailor(["run".as_ref(), app_path.as_os_str()]));
+}
+
+#[test]
+fn test_build_options_schema() {
+    let test_dir = setup_test_dir("options_schema");
+    let test_path = &test_dir.path;
+    let lib_path = test_path.join("schema_lib");
+    let app_path = test_path.join("schema_app");
+
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        r#"name = "schema_lib"
+version = "0.1.0"
+edition = "2026.1"
+type = "lib"
+
+[options-schema.level]
+type = "integer"
+default = 6
+range = [0, 9]
+description = "Compression level"
+
+[options-schema.strategy]
+type = "string"
+values = ["default", "rle"]
+
+[options-schema.fast]
+type = "boolean"
+default = false
+"#,
+    );
+    // A declared boolean is defined either way, to 0 when false
+    write_file(
+        &lib_path.join("src/schema.c"),
+        "#include <string.h>\n\n#ifndef FAST\n#error FAST is undefined\n#endif\n\nint schema(void) { return LEVEL * 10 + (strcmp(STRATEGY, \"rle\") == 0) + FAST * 100; }\n",
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "int schema(void);\n\nint main() { return schema() != 31; }\n",
+    );
+    let build_with = |options: &str| {
+        write_file(
+            &app_path.join("Tailor.toml"),
+            &format!(
+                "name = \"schema_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\nschema_lib = {{ path = \"../schema_lib\" }}\n\n[schema_lib.options]\n{}\n",
+                options
+            ),
+        );
+        tailor(["run".as_ref(), app_path.as_os_str()])
+    };
+    let assert_rejected = |output: std::process::Output, expected: &str| {
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(expected), "Got stderr: {}", stderr);
+    };
+
+    assert_success(&build_with("level = 3\nstrategy = \"rle\""));
+    assert!(
+        !build_with("level = 3\nstrategy = \"rle\"\nfast = true")
+            .status
+            .success()
+    );
+
+    assert_rejected(
+        build_with("levle = 3"),
+        "unknown option `levle`; available options are `fast`, `level`, `strategy`",
+    );
+    assert_rejected(
+        build_with("level = \"3\""),
+        "invalid value for option `level`: expected an integer, found `\"3\"`",
+    );
+    assert_rejected(
+        build_with("level = 12"),
+        "invalid value for option `level`: 12 is out of range 0 to 9",
+    );
+    assert_rejected(
+        build_with("strategy = \"huffman\""),
+        "invalid value for option `strategy`: \"huffman\" is not one of \"default\", \"rle\"",
+    );
+    assert_rejected(
+        build_with("level = [1, 2]"),
+        "option `level` of 'schema_lib' must be a string, number or boolean, found array",
+    );
+
+    // `info` lists the available options and their resolved values
+    build_with("strategy = \"rle\"");
+    let output = tailor(["info".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("schema_lib v0.1.0 (lib)"),
+        "Got stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("level (integer, default 6, range 0 to 9) = 6"),
+        "Got stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Compression level"),
+        "Got stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("strategy (string, one of \"default\", \"rle\") = \"rle\""),
+        "Got stdout: {}",
+        stdout
+    );
+
+    // Names that only differ in case would define the same name
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"schema_lib\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[options-schema.level]\ntype = \"integer\"\n\n[options-schema.LEVEL]\ntype = \"integer\"\n",
+    );
+    assert_rejected(build_with(""), "both define `LEVEL`");
+}
+
+#[test]
+fn test_build_features() {
+    let test_dir = setup_test_dir("features");
+    let test_path = &test_dir.path;
+    let codec_path = test_path.join("feat_codec");
+    let extra_path = test_path.join("feat_extra");
+    let app_path = test_path.join("feat_app");
+
+    write_file(
+        &codec_path.join("Tailor.toml"),
+        "name = \"feat_codec\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[features]\ndefault = [\"std\"]\nstd = []\nfast = []\n",
+    );
+    write_file(
+        &codec_path.join("src/codec.c"),
+        "int codec(void) {\n    int features = 0;\n#ifdef TAILOR_FEATURE_STD\n    features += 1;\n#endif\n#ifdef TAILOR_FEATURE_FAST\n    features += 2;\n#endif\n    return features;\n}\n",
+    );
+    // feat_extra asks for `std` of feat_codec, which the app turns off
+    write_file(
+        &extra_path.join("Tailor.toml"),
+        &format!(
+            "name = \"feat_extra\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[dependencies]\nfeat_codec = {{ path = \"{}\", default-features = false, features = [\"std\"] }}\n",
+            codec_path.display()
+        ),
+    );
+    write_file(
+        &extra_path.join("src/extra.c"),
+        "int extra(void) { return 40; }\n",
+    );
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"feat_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\nfeat_codec = { path = \"../feat_codec\", default-features = false }\nfeat_extra = { path = \"../feat_extra\", optional = true }\n\n[features]\nturbo = [\"feat_codec/fast\", \"dep:feat_extra\"]\n",
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "#include <stdio.h>\n\nint codec(void);\n#ifdef TAILOR_FEATURE_TURBO\nint extra(void);\n#endif\n\nint main() {\n    printf(\"codec=%d\\n\", codec());\n#ifdef TAILOR_FEATURE_TURBO\n    printf(\"extra=%d\\n\", extra());\n#endif\n    return 0;\n}\n",
+    );
+
+    // Without features, the optional dependency is left out of the graph
+    let output = tailor(["run".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("codec=0"), "Got stdout: {}", stdout);
+    assert!(!stdout.contains("extra="), "Got stdout: {}", stdout);
+    assert!(!app_path.join("build/debug/obj/feat_extra@0.1.0").exists());
+    let lockfile =
+        fs::read_to_string(app_path.join("Tailor.lock")).expect("Failed to read Tailor.lock");
+    assert!(
+        !lockfile.contains("feat_extra"),
+        "Got lockfile: {}",
+        lockfile
+    );
+
+    // `turbo` enables feat_extra and `fast`; feat_codec is built once with
+    // the union of the features its dependents request
+    for flags in [&["--features", "turbo"][..], &["--all-features"]] {
+        let mut args = vec![std::ffi::OsStr::new("run")];
+        args.extend(flags.iter().map(std::ffi::OsStr::new));
+        args.push(app_path.as_os_str());
+        let output = tailor(args);
+        assert_success(&output);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("codec=3"), "Got stdout: {}", stdout);
+        assert!(stdout.contains("extra=40"), "Got stdout: {}", stdout);
+        // The lockfile keeps the graph of the default features
+        let lockfile =
+            fs::read_to_string(app_path.join("Tailor.lock")).expect("Failed to read Tailor.lock");
+        assert!(
+            !lockfile.contains("feat_extra"),
+            "Got lockfile: {}",
+            lockfile
+        );
+    }
+
+    let output = tailor([
+        "build".as_ref(),
+        "--features".as_ref(),
+        "nope".as_ref(),
+        app_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("feat_app@0.1.0: no feature `nope`; available features are `turbo`"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    let output = tailor(["info".as_ref(), app_path.as_os_str()]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("features: -turbo"),
+        "Got stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("features: -default -fast -std"),
+        "Got stdout: {}",
+        stdout
+    );
+
+    // A disabled optional dependency is never fetched, even when it cannot be
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"feat_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\nfeat_codec = { path = \"../feat_codec\", default-features = false }\nfeat_extra = { path = \"../feat_extra\", optional = true }\nfeat_missing = { path = \"../does-not-exist\", optional = true }\n\n[features]\nturbo = [\"feat_codec/fast\", \"dep:feat_extra\"]\nlegacy = [\"dep:feat_missing\"]\n",
+    );
+    assert_success(&tailor(["build".as_ref(), app_path.as_os_str()]));
+    let output = tailor([
+        "run".as_ref(),
+        "--features".as_ref(),
+        "turbo".as_ref(),
+        app_path.as_os_str(),
+    ]);
+    assert_success(&output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("extra=40"));
+    let output = tailor([
+        "build".as_ref(),
+        "--features".as_ref(),
+        "legacy".as_ref(),
+        app_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_build_config_header() {
+    let test_dir = setup_test_dir("config_header");
+    let test_path = &test_dir.path;
+    let lib_path = test_path.join("cfg_lib");
+    let app_path = test_path.join("cfg_app");
+
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"cfg_lib\"\nversion = \"0.2.0\"\nedition = \"2026.1\"\ntype = \"lib\"\nconfig-header = true\n\n[options]\nlevel = 1\n\n[features]\ndefault = [\"fast\"]\nfast = []\n",
+    );
+    // The public header includes the generated one, so dependents see it too
+    write_file(
+        &lib_path.join("include/cfg_lib.h"),
+        "#include \"cfg_lib_config.h\"\n\n#define CFG_LIB_LEVEL LEVEL\n\nint cfg_level(void);\n",
+    );
+    write_file(
+        &lib_path.join("src/cfg_lib.c"),
+        "#include \"cfg_lib.h\"\n\n#ifndef TAILOR_FEATURE_FAST\n#error features missing from the config header\n#endif\n\nint cfg_level(void) { return LEVEL; }\n",
+    );
+    let manifest = "name = \"cfg_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\nconfig-header = true\n\n[dependencies]\ncfg_lib = { path = \"../cfg_lib\" }\n\n[cfg_lib.options]\nlevel = 2\n\n[options]\n";
+    write_file(
+        &app_path.join("Tailor.toml"),
+        &format!("{}greeting = \"hi\"\n", manifest),
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "#include <string.h>\n#include \"cfg_app_config.h\"\n#include \"cfg_lib.h\"\n\nint util(void);\n\nint main() { return cfg_level() != CFG_LIB_LEVEL || strcmp(CFG_APP_PKG_NAME, \"cfg_app\") != 0 || util(); }\n",
+    );
+    write_file(
+        &app_path.join("src/util.c"),
+        "int util(void) { return 0; }\n",
+    );
+
+    assert_success(&tailor(["run".as_ref(), app_path.as_os_str()]));
+    let header = app_path.join("build/debug/gen/cfg_lib/cfg_lib_config.h");
+    let content = fs::read_to_string(&header).expect("config header was not generated");
+    for line in [
+        "#define CFG_LIB_PKG_NAME \"cfg_lib\"",
+        "#define CFG_LIB_PKG_VERSION \"0.2.0\"",
+        "#define CFG_LIB_PKG_EDITION \"2026.1\"",
+        "#define LEVEL 2",
+        "#define TAILOR_FEATURE_FAST 1",
+    ] {
+        assert!(content.contains(line), "Got header: {}", content);
+    }
+    let compile_commands =
+        fs::read_to_string(app_path.join("build/debug/compile_commands.json")).unwrap();
+    assert!(!compile_commands.contains("-DLEVEL"));
+    assert!(!compile_commands.contains("-DGREETING"));
+
+    // An unchanged header is not rewritten
+    let app_header = app_path.join("build/debug/gen/cfg_app/cfg_app_config.h");
+    let generated = modified(&app_header);
+    let main_object = app_path.join("build/debug/obj/cfg_app@0.1.0/src/main.o");
+    let util_object = app_path.join("build/debug/obj/cfg_app@0.1.0/src/util.o");
+    let util_built = modified(&util_object);
+    assert_success(&tailor(["build".as_ref(), app_path.as_os_str()]));
+    assert_eq!(generated, modified(&app_header));
+
+    // Changing an option only recompiles the sources including the header
+    let main_built = modified(&main_object);
+    write_file(
+        &app_path.join("Tailor.toml"),
+        &format!("{}greeting = \"hello\"\n", manifest),
+    );
+    assert_success(&tailor(["build".as_ref(), app_path.as_os_str()]));
+    assert!(
+        fs::read_to_string(&app_header)
+            .unwrap()
+            .contains("#define GREETING \"hello\"")
+    );
+    assert_ne!(main_built, modified(&main_object));
+    assert_eq!(util_built, modified(&util_object));
+}
+
+#[test]
+fn test_build_header_only_dependencies() {
+    let test_dir = setup_test_dir("header_only");
+    let test_path = &test_dir.path;
+    let base_path = test_path.join("hdr_base");
+    let vec_path = test_path.join("hdr_vec");
+    let app_path = test_path.join("hdr_app");
+
+    assert_success(&tailor([
+        "new".as_ref(),
+        "--header-only".as_ref(),
+        base_path.as_os_str(),
+    ]));
+    write_file(
+        &base_path.join("include/hdr_base/hdr_base.h"),
+        "#define HDR_BASE_ANSWER 42\n",
+    );
+    // A header-only library with options and features, on top of another one
+    write_file(
+        &vec_path.join("Tailor.toml"),
+        &format!(
+            "name = \"hdr_vec\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"header-only\"\n\n[dependencies]\nhdr_base = {{ path = \"{}\" }}\n\n[options]\ncapacity = 8\n\n[features]\ndefault = [\"checked\"]\nchecked = []\n",
+            base_path.display()
+        ),
+    );
+    write_file(
+        &vec_path.join("include/hdr_vec/hdr_vec.h"),
+        "#include \"hdr_base/hdr_base.h\"\n\n#ifndef TAILOR_FEATURE_CHECKED\n#error features of header-only dependencies are missing\n#endif\n\nstatic inline int hdr_vec_capacity(void) { return CAPACITY + HDR_BASE_ANSWER; }\n",
+    );
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"hdr_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\nhdr_vec = { path = \"../hdr_vec\" }\n\n[hdr_vec.options]\ncapacity = 16\n",
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "#include \"hdr_vec/hdr_vec.h\"\n\nint main() { return hdr_vec_capacity() != 58; }\n",
+    );
+
+    assert_success(&tailor(["run".as_ref(), app_path.as_os_str()]));
+    assert!(!app_path.join("build/debug/deps").exists());
+
+    // The include directories of an optional dependency enabled by a dependent are visible too
+    write_file(
+        &vec_path.join("Tailor.toml"),
+        &format!(
+            "name = \"hdr_vec\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"header-only\"\n\n[dependencies]\nhdr_base = {{ path = \"{}\", optional = true }}\n\n[options]\ncapacity = 8\n\n[features]\ndefault = [\"checked\"]\nchecked = []\nbase = [\"dep:hdr_base\"]\n",
+            base_path.display()
+        ),
+    );
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"hdr_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\nhdr_vec = { path = \"../hdr_vec\", features = [\"base\"] }\n\n[hdr_vec.options]\ncapacity = 16\n",
+    );
+    assert_success(&tailor(["run".as_ref(), app_path.as_os_str()]));
+
+    // Building a header-only package on its own produces nothing to link
+    assert_success(&tailor(["build".as_ref(), base_path.as_os_str()]));
+    assert!(find_files(&base_path.join("build"), "a").is_empty());
+
+    // A regular library without sources points at `header-only`
+    write_file(
+        &base_path.join("Tailor.toml"),
+        "name = \"hdr_base\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+    );
+    let output = tailor(["build".as_ref(), base_path.as_os_str()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("hdr_base@0.1.0 has no sources") && stderr.contains("header-only"),
+        "Got stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_run_bins_and_examples() {
+    let test_dir = setup_test_dir("bins_and_examples");
+    let lib_path = test_dir.path.join("multi_lib");
+    let app_path = test_dir.path.join("multi_app");
+
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"multi_lib\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[[bin]]\nname = \"bench\"\nmain = \"tools/bench.c\"\nsources = [\"tools/timer.c\"]\n",
+    );
+    write_file(
+        &lib_path.join("src/multi.c"),
+        "int multi(void) { return 7; }\n",
+    );
+    write_file(
+        &lib_path.join("src/bin/tool.c"),
+        "#include <stdio.h>\n\nint multi(void);\n\nint main() { printf(\"tool %d\\n\", multi()); return 0; }\n",
+    );
+    write_file(
+        &lib_path.join("tools/bench.c"),
+        "#include <stdio.h>\n\nint multi(void);\nint timer(void);\n\nint main() { printf(\"bench %d\\n\", multi() + timer()); return 0; }\n",
+    );
+    write_file(
+        &lib_path.join("tools/timer.c"),
+        "int timer(void) { return 3; }\n",
+    );
+    write_file(
+        &lib_path.join("examples/demo.c"),
+        "#include <stdio.h>\n\nint multi(void);\n\nint main() { printf(\"demo %d\\n\", multi()); return 0; }\n",
+    );
+
+    let run = |args: &[&str], path: &Path| {
+        let mut command = vec![std::ffi::OsStr::new("run")];
+        command.extend(args.iter().map(std::ffi::OsStr::new));
+        command.push(path.as_os_str());
+        tailor(command)
+    };
+
+    // A library has no default binary to run
+    let output = run(&[], &lib_path);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--bin bench, --bin tool, --example demo"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // Binaries link the library's code; examples are only built when asked for
+    for (args, expected) in [
+        (&["--bin", "tool"], "tool 7"),
+        (&["--bin", "bench"], "bench 10"),
+        (&["--example", "demo"], "demo 7"),
+    ] {
+        let output = run(args, &lib_path);
+        assert_success(&output);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains(expected), "Got stdout: {}", stdout);
+        if args[0] == "--bin" {
+            assert!(!lib_path.join("build/debug/examples").exists());
+        }
+    }
+    assert!(lib_path.join("build/debug/bin/tool").exists());
+    assert!(lib_path.join("build/debug/examples/demo").exists());
+
+    let output = run(&["--bin", "nope"], &lib_path);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("no bin target named `nope`; available bin targets are `bench`, `tool`"),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // In a bin package, other binaries share every source but `src/main.c`
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"multi_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n",
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "#include <stdio.h>\n\nint util(void);\n\nint main() { printf(\"main %d\\n\", util()); return 0; }\n",
+    );
+    write_file(
+        &app_path.join("src/util.c"),
+        "int util(void) { return 1; }\n",
+    );
+    write_file(
+        &app_path.join("src/bin/extra.c"),
+        "#include <stdio.h>\n\nint util(void);\n\nint main() { printf(\"extra %d\\n\", util()); return 0; }\n",
+    );
+    for (args, expected) in [
+        (&[][..], "main 1"),
+        (&["--bin", "multi_app"], "main 1"),
+        (&["--bin", "extra"], "extra 1"),
+    ] {
+        let output = run(args, &app_path);
+        assert_success(&output);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains(expected), "Got stdout: {}", stdout);
+    }
+}
+
+#[test]
+fn test_build_workspace() {
+    let test_dir = setup_test_dir("workspace");
+    let root = &test_dir.path;
+    let math_path = root.join("packages/ws_math");
+    let app_path = root.join("packages/ws_app");
+
+    write_file(
+        &root.join("Tailor.toml"),
+        "[workspace]\nmembers = [\"packages/*\"]\n",
+    );
+    write_file(
+        &math_path.join("Tailor.toml"),
+        "name = \"ws_math\"\nversion = \"0.2.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+    );
+    write_file(
+        &math_path.join("include/ws_math/ws_math.h"),
+        "int ws_math_add(int a, int b);\n",
+    );
+    write_file(
+        &math_path.join("src/ws_math.c"),
+        "#include \"ws_math/ws_math.h\"\n\nint ws_math_add(int a, int b) { return a + b; }\n",
+    );
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"ws_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\nws_math = { path = \"../ws_math\" }\n",
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "#include <stdio.h>\n#include \"ws_math/ws_math.h\"\n\nint main() { printf(\"sum %d\\n\", ws_math_add(2, 3)); return 0; }\n",
+    );
+
+    // Every member builds into the shared build directory, with one lockfile
+    assert_success(&tailor(["build".as_ref(), root.as_os_str()]));
+    assert!(root.join("build/debug/ws_app@0.1.0").exists());
+    assert!(root.join("build/debug/libws_math.a").exists());
+    assert!(!app_path.join("build").exists() && !math_path.join("build").exists());
+    assert!(!app_path.join("Tailor.lock").exists());
+    let lockfile =
+        fs::read_to_string(root.join("Tailor.lock")).expect("Failed to read Tailor.lock");
+    assert_eq!(
+        lockfile,
+        "# This file is generated by tailor; do not edit.\nversion = 1\n\n[[package]]\nname = \"ws_app\"\nversion = \"0.1.0\"\ndependencies = [\"ws_math 0.2.0\"]\n\n[[package]]\nname = \"ws_math\"\nversion = \"0.2.0\"\n"
+    );
+
+    // Members are used in place: building again, even from a member, compiles nothing
+    let object = root.join("build/debug/obj/ws_math@0.2.0/src/ws_math.o");
+    let before = modified(&object);
+    assert_success(&tailor(["build".as_ref(), app_path.as_os_str()]));
+    assert_eq!(modified(&object), before);
+
+    // `-p` selects members, from the root or from another member
+    let output = tailor([
+        "run".as_ref(),
+        "-p".as_ref(),
+        "ws_app".as_ref(),
+        root.as_os_str(),
+    ]);
+    assert_success(&output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("sum 5"));
+    // Building some members keeps what is locked for the others
+    assert_success(&tailor([
+        "build".as_ref(),
+        "-p".as_ref(),
+        "ws_math".as_ref(),
+        root.as_os_str(),
+    ]));
+    assert_eq!(
+        fs::read_to_string(root.join("Tailor.lock")).expect("Failed to read Tailor.lock"),
+        lockfile
+    );
+    let output = tailor(["run".as_ref(), root.as_os_str()]);
+    assert!(!output.status.success());
+    let output = tailor([
+        "build".as_ref(),
+        "-p".as_ref(),
+        "nope".as_ref(),
+        root.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "package `nope` is not a member of the workspace; members are `ws_app`, `ws_math`"
+        ),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // Cleaning one member keeps the others' artifacts
+    assert_success(&tailor([
+        "clean".as_ref(),
+        "--package=ws_math".as_ref(),
+        math_path.as_os_str(),
+    ]));
+    assert!(!object.exists() && !root.join("build/debug/libws_math.a").exists());
+    assert!(root.join("build/debug/ws_app@0.1.0").exists());
+    assert_success(&tailor(["clean".as_ref(), root.as_os_str()]));
+    assert!(!root.join("build").exists());
+}
+
+#[test]
+fn test_test_runs_discovered_tests() {
+    let test_dir = setup_test_dir("test_command");
+    let lib_path = test_dir.path.join("tested_lib");
+    let app_path = test_dir.path.join("tested_app");
+
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"tested_lib\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+    );
+    write_file(
+        &lib_path.join("src/tested.c"),
+        "int tested_add(int a, int b) { return a + b; }\n",
+    );
+    write_file(
+        &lib_path.join("tests/add.c"),
+        "int tested_add(int a, int b);\n\nint main() { return tested_add(2, 3) != 5; }\n",
+    );
+    write_file(
+        &lib_path.join("tests/broken.c"),
+        "#include <stdio.h>\n#include <unistd.h>\n\nint main() {\n    printf(\"expected 5\\n\");\n    fflush(stdout);\n    if (fork() == 0) sleep(20);\n    return 2;\n}\n",
+    );
+    // The hanging test's child holds its pipes too, and is killed with it
+    write_file(
+        &lib_path.join("tests/hangs.c"),
+        "#include <stdio.h>\n#include <unistd.h>\n\nint main() {\n    printf(\"waiting\\n\");\n    fflush(stdout);\n    fork();\n    sleep(30);\n    return 0;\n}\n",
+    );
+
+    let test = |args: &[&str], path: &Path| {
+        let mut command = vec![std::ffi::OsStr::new("test")];
+        command.extend(args.iter().map(std::ffi::OsStr::new));
+        command.insert(1, path.as_os_str());
+        tailor(command)
+    };
+
+    // Failures and timeouts are reported with their output, and fail the
+    // command without waiting for the processes the tests left behind
+    let start = std::time::Instant::now();
+    let output = test(&["--timeout", "1"], &lib_path);
+    assert!(start.elapsed() < std::time::Duration::from_secs(15));
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        "Running\x1B[0m 3 tests",
+        "test add ... ok",
+        "test broken ... FAILED",
+        "test hangs ... TIMEOUT",
+        "---- broken (exit code 2) ----\nexpected 5",
+        "---- hangs (timed out after 1s) ----\nwaiting",
+        "test result: FAILED. 1 passed; 2 failed; 0 filtered out",
+    ] {
+        assert!(
+            stdout.contains(expected),
+            "Missing {:?} in stdout: {}",
+            expected,
+            stdout
+        );
+    }
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2 tests failed"));
+    assert!(
+        lib_path
+            .join("build/debug/tests/tested_lib@0.1.0/add")
+            .exists()
+    );
+
+    // A filter only builds and runs the matching tests
+    fs::remove_dir_all(lib_path.join("build")).expect("Failed to remove build directory");
+    let output = test(&["--", "ad"], &lib_path);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("test result: ok. 1 passed; 0 failed; 2 filtered out"),
+        "Got stdout: {}",
+        stdout
+    );
+    assert!(
+        !lib_path
+            .join("build/debug/tests/tested_lib@0.1.0/broken")
+            .exists()
+    );
+
+    // Tests of a bin package share every source but `src/main.c`
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"tested_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n",
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "int twice(int x);\n\nint main() { return twice(0); }\n",
+    );
+    write_file(
+        &app_path.join("src/twice.c"),
+        "int twice(int x) { return 2 * x; }\n",
+    );
+    write_file(
+        &app_path.join("tests/twice.c"),
+        "int twice(int x);\n\nint main() { return twice(2) != 4; }\n",
+    );
+    let output = test(&[], &app_path);
+    assert_success(&output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("test twice ... ok"));
+}
+
+#[test]
+fn test_dev_dependencies() {
+    let test_dir = setup_test_dir("dev_dependencies");
+    let check_path = test_dir.path.join("dev_check");
+    let lib_path = test_dir.path.join("dev_lib");
+    let app_path = test_dir.path.join("dev_app");
+
+    // A tiny assertion library, only needed by the tests and examples of dev_lib
+    write_file(
+        &check_path.join("Tailor.toml"),
+        "name = \"dev_check\"\nversion = \"0.3.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+    );
+    write_file(
+        &check_path.join("include/dev_check/dev_check.h"),
+        "int dev_check_equal(int expected, int actual);\n",
+    );
+    write_file(
+        &check_path.join("src/dev_check.c"),
+        "#include \"dev_check/dev_check.h\"\n\nint dev_check_equal(int expected, int actual) { return expected == actual; }\n",
+    );
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"dev_lib\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[dev-dependencies]\ndev_check = { path = \"../dev_check\" }\n",
+    );
+    write_file(
+        &lib_path.join("include/dev_lib/dev_lib.h"),
+        "int dev_lib_square(int x);\n",
+    );
+    write_file(
+        &lib_path.join("src/dev_lib.c"),
+        "#include \"dev_lib/dev_lib.h\"\n\nint dev_lib_square(int x) { return x * x; }\n",
+    );
+    write_file(
+        &lib_path.join("tests/square.c"),
+        "#include \"dev_check/dev_check.h\"\n#include \"dev_lib/dev_lib.h\"\n\nint main() { return !dev_check_equal(9, dev_lib_square(3)); }\n",
+    );
+    write_file(
+        &lib_path.join("examples/show.c"),
+        "#include <stdio.h>\n#include \"dev_check/dev_check.h\"\n#include \"dev_lib/dev_lib.h\"\n\nint main() { printf(\"checked %d\\n\", dev_check_equal(4, dev_lib_square(2))); return 0; }\n",
+    );
+
+    // Tests and examples build and link against the dev-dependency
+    let output = tailor(["test".as_ref(), lib_path.as_os_str()]);
+    assert_success(&output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("test square ... ok"));
+    let output = tailor([
+        "run".as_ref(),
+        "--example".as_ref(),
+        "show".as_ref(),
+        lib_path.as_os_str(),
+    ]);
+    assert_success(&output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("checked 1"));
+    assert!(
+        fs::read_to_string(lib_path.join("Tailor.lock"))
+            .expect("Failed to read Tailor.lock")
+            .contains("name = \"dev_check\"")
+    );
+
+    // A plain build of the library does not resolve it, but keeps it locked
+    fs::remove_dir_all(lib_path.join("build")).expect("Failed to remove build directory");
+    let lockfile =
+        fs::read_to_string(lib_path.join("Tailor.lock")).expect("Failed to read Tailor.lock");
+    assert_success(&tailor(["build".as_ref(), lib_path.as_os_str()]));
+    assert!(!lib_path.join("build/debug/deps/dev_check@0.3.0").exists());
+    assert_eq!(
+        fs::read_to_string(lib_path.join("Tailor.lock")).expect("Failed to read Tailor.lock"),
+        lockfile
+    );
+
+    // ... not even when one of them cannot be found
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"dev_lib\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[dev-dependencies]\ndev_check = { path = \"../dev_check\" }\nmissing = { path = \"../does-not-exist\" }\n",
+    );
+    assert_success(&tailor(["build".as_ref(), lib_path.as_os_str()]));
+    assert!(
+        !tailor(["test".as_ref(), lib_path.as_os_str()])
+            .status
+            .success()
+    );
+
+    // A dev-dependency named like a table key is not taken for `[<name>.options]`
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"dev_lib\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[dev-dependencies]\noptions = { path = \"../does-not-exist\", features = [] }\n",
+    );
+    assert_success(&tailor(["build".as_ref(), lib_path.as_os_str()]));
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"dev_lib\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[dev-dependencies]\ndev_check = { path = \"../dev_check\" }\n",
+    );
+
+    // Dependents never see the library's dev-dependencies
+    write_file(
+        &app_path.join("Tailor.toml"),
+        "name = \"dev_app\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\n\n[dependencies]\ndev_lib = { path = \"../dev_lib\" }\n\n[dev-dependencies]\ndev_check = { path = \"../dev_check\" }\n",
+    );
+    write_file(
+        &app_path.join("src/main.c"),
+        "#include \"dev_lib/dev_lib.h\"\n\nint main() { return dev_lib_square(0); }\n",
+    );
+    write_file(
+        &app_path.join("tests/app.c"),
+        "#include \"dev_check/dev_check.h\"\n#include \"dev_lib/dev_lib.h\"\n\nint main() { return !dev_check_equal(16, dev_lib_square(4)); }\n",
+    );
+    assert_success(&tailor(["build".as_ref(), app_path.as_os_str()]));
+    assert!(!app_path.join("build/debug/deps/dev_check@0.3.0").exists());
+    assert_success(&tailor(["test".as_ref(), app_path.as_os_str()]));
+
+    // ... and neither does the package's own binary
+    write_file(
+        &app_path.join("src/main.c"),
+        "#include \"dev_check/dev_check.h\"\n\nint main() { return !dev_check_equal(1, 1); }\n",
+    );
+    let output = tailor(["test".as_ref(), app_path.as_os_str()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("dev_check/dev_check.h"),
+        "Got stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_test_reports() {
+    let test_dir = setup_test_dir("test_reports");
+    let lib_path = test_dir.path.join("reported_lib");
+
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"reported_lib\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+    );
+    write_file(
+        &lib_path.join("src/reported.c"),
+        "int reported_add(int a, int b) { return a + b; }\n",
+    );
+    write_file(
+        &lib_path.join("tests/cases.c"),
+        r#"#include <tailor_test.h>
+
+int reported_add(int a, int b);
+
+TAILOR_TEST(adds) { TAILOR_ASSERT_EQ(5, reported_add(2, 3)); }
+TAILOR_TEST(adds_wrong) { TAILOR_ASSERT_EQ(6, reported_add(2, 3)); }
+TAILOR_TEST(not_yet) { TAILOR_SKIP("later"); }
+
+int main(void) {
+  TAILOR_RUN(adds);
+  TAILOR_RUN(adds_wrong);
+  TAILOR_RUN(not_yet);
+  return TAILOR_RESULT();
+}
+"#,
+    );
+    // The output of a Unity test program
+    write_file(
+        &lib_path.join("tests/unity.c"),
+        r#"#include <stdio.h>
+
+int main(void) {
+  printf("tests/unity.c:4:test_passes:PASS\n");
+  printf("tests/unity.c:9:test_compares:FAIL: Expected 1 Was <2>\n");
+  return 1;
+}
+"#,
+    );
+    // ... and of a greatest one, in verbose mode
+    write_file(
+        &lib_path.join("tests/greatest.c"),
+        r#"#include <stdio.h>
+
+int main(void) {
+  printf("\n* Suite greatest:\n");
+  printf("PASS greatest_adds: \n");
+  printf("SKIP greatest_later: not yet\n");
+  printf("\nTotal: 2 tests (12 ticks, 0.000 sec), 1 assertions\n");
+  printf("Pass: 1, fail: 0, skip: 1.\n");
+  return 0;
+}
+"#,
+    );
+    // Without greatest's summary, lines that look like its cases are plain output
+    write_file(
+        &lib_path.join("tests/plain.c"),
+        "#include <stdio.h>\n\nint main(void) { printf(\"PASS checked: all good\\n\"); return 0; }\n",
+    );
+
+    // A JUnit report records each executable and case; the summary stays on stdout
+    let report_path = test_dir.path.join("report.xml");
+    let output = tailor([
+        std::ffi::OsStr::new("test"),
+        lib_path.as_os_str(),
+        std::ffi::OsStr::new("--format=junit"),
+        std::ffi::OsStr::new("--output"),
+        report_path.as_os_str(),
+    ]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("test result: FAILED. 2 passed; 2 failed"),
+        "Got stdout: {}",
+        stdout
+    );
+    let report = fs::read_to_string(&report_path).expect("Failed to read the JUnit report");
+    for expected in [
+        "<testsuites name=\"reported_lib\" tests=\"8\" failures=\"2\" skipped=\"2\"",
+        "<testsuite name=\"cases\" tests=\"3\" failures=\"1\" skipped=\"1\"",
+        "<testcase name=\"adds\" classname=\"cases\" time=\"",
+        "tests/cases.c:6: expected reported_add(2, 3) == 6, got 5\">",
+        "<skipped message=\"later\"/>",
+        "<testcase name=\"test_compares\" classname=\"unity\">",
+        "<failure message=\"tests/unity.c:9: Expected 1 Was &lt;2&gt;\">",
+        "<testcase name=\"greatest_later\" classname=\"greatest\">",
+        "<skipped message=\"not yet\"/>",
+        "<testcase name=\"plain\" classname=\"plain\" time=\"",
+        "<system-out>PASS checked: all good",
+        "<system-out>tailor-test: ok adds 0.",
+    ] {
+        assert!(
+            report.contains(expected),
+            "Missing {:?} in report: {}",
+            expected,
+            report
+        );
+    }
+
+    // A TAP report without --output takes stdout over from the summary
+    let output = tailor([
+        std::ffi::OsStr::new("test"),
+        lib_path.as_os_str(),
+        std::ffi::OsStr::new("--format"),
+        std::ffi::OsStr::new("tap"),
+    ]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("TAP version 13\n1..8\n"),
+        "Got stdout: {}",
+        stdout
+    );
+    for expected in [
+        "ok 1 - cases: adds\n  ---\n  duration_ms: ",
+        "not ok 2 - cases: adds_wrong\n",
+        "tests/cases.c:6: expected reported_add(2, 3) == 6, got 5\"\n",
+        "ok 3 - cases: not_yet # SKIP later\n",
+        "ok 4 - greatest: greatest_adds\n",
+        "ok 5 - greatest: greatest_later # SKIP not yet\n",
+        "ok 6 - plain\n",
+        "not ok 8 - unity: test_compares\n",
+    ] {
+        assert!(
+            stdout.contains(expected),
+            "Missing {:?} in stdout: {}",
+            expected,
+            stdout
+        );
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("test result: FAILED."),
+        "Got stderr: {}",
+        stderr
+    );
+
+    // Reports are test options only
+    let output = tailor([
+        std::ffi::OsStr::new("test"),
+        lib_path.as_os_str(),
+        std::ffi::OsStr::new("--format=xml"),
+    ]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Usage"));
+}
+
+#[test]
+fn test_test_coverage() {
+    let test_dir = setup_test_dir("test_coverage");
+    let dep_path = test_dir.path.join("covered_dep");
+    let lib_path = test_dir.path.join("covered_lib");
+
+    write_file(
+        &dep_path.join("Tailor.toml"),
+        "name = \"covered_dep\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+    );
+    write_file(
+        &dep_path.join("src/twice.c"),
+        "int covered_twice(int x) { return 2 * x; }\n",
+    );
+    write_file(
+        &lib_path.join("Tailor.toml"),
+        "name = \"covered_lib\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n\n[dependencies]\ncovered_dep = { path = \"../covered_dep\" }\n",
+    );
+    write_file(
+        &lib_path.join("src/clamp.c"),
+        r#"int covered_twice(int x);
+
+int covered_clamp(int x) {
+  if (x < 0)
+    return 0;
+  if (x > 10)
+    return 10;
+  return covered_twice(x);
+}
+
+int covered_unused(void) { return 42; }
+"#,
+    );
+    write_file(
+        &lib_path.join("tests/clamp.c"),
+        "int covered_clamp(int x);\n\nint main(void) { return covered_clamp(-1) != 0 || covered_clamp(2) != 4; }\n",
+    );
+
+    let coverage_path = lib_path.join("build/debug+coverage/coverage");
+    let test = |args: &[&str]| {
+        let mut command = vec![std::ffi::OsStr::new("test"), lib_path.as_os_str()];
+        command.extend(args.iter().map(std::ffi::OsStr::new));
+        tailor(command)
+    };
+
+    // The package's own sources are measured, not its tests nor its dependencies
+    for _ in 0..2 {
+        let output = test(&["--coverage"]);
+        assert_success(&output);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        for expected in [
+            "File ",
+            "src/clamp.c",
+            "5/7  71.43%          1/2  50.00%          3/4  75.00%",
+            "Coverage\x1B[0m written to ",
+        ] {
+            assert!(
+                stdout.contains(expected),
+                "Missing {:?} in stdout: {}",
+                expected,
+                stdout
+            );
+        }
+        assert!(!stdout.contains("/src/twice.c"), "Got stdout: {}", stdout);
+        assert!(!stdout.contains("tests/clamp.c"), "Got stdout: {}", stdout);
+
+        // Counts are those of the last run only
+        let lcov =
+            fs::read_to_string(coverage_path.join("lcov.info")).expect("Failed to read lcov.info");
+        for expected in [
+            "/covered_lib/src/clamp.c\n",
+            "FN:3,covered_clamp\n",
+            "FNDA:2,covered_clamp\nFNDA:0,covered_unused\n",
+            "BRDA:6,0,0,0\n",
+            "DA:4,2\n",
+            "DA:7,0\n",
+            "LF:7\nLH:5\nend_of_record\n",
+        ] {
+            assert!(
+                lcov.contains(expected),
+                "Missing {:?} in lcov.info: {}",
+                expected,
+                lcov
+            );
+        }
+    }
+    let cobertura = fs::read_to_string(coverage_path.join("cobertura.xml"))
+        .expect("Failed to read cobertura.xml");
+    for expected in [
+        "<coverage line-rate=\"0.7143\" branch-rate=\"0.7500\"",
+        "<package name=\"covered_lib\"",
+        "<class name=\"src/clamp.c\" filename=\"src/clamp.c\"",
+        "<line number=\"6\" hits=\"1\" branch=\"true\" condition-coverage=\"50% (1/2)\"/>",
+    ] {
+        assert!(
+            cobertura.contains(expected),
+            "Missing {:?} in cobertura.xml: {}",
+            expected,
+            cobertura
+        );
+    }
+    // The coverage build does not touch the regular one
+    assert!(!lib_path.join("build/debug").exists());
+
+    // Dependencies are measured when asked for
+    let output = test(&["--coverage", "--coverage-deps"]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("/src/twice.c"), "Got stdout: {}", stdout);
+
+    // Notes left by a removed source are not measured
+    write_file(
+        &lib_path.join("src/removed.c"),
+        "int covered_removed(void) { return 1; }\n",
+    );
+    assert_success(&test(&["--coverage"]));
+    fs::remove_file(lib_path.join("src/removed.c")).expect("Failed to remove a source");
+    let output = test(&["--coverage"]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("src/removed.c"), "Got stdout: {}", stdout);
+
+    // In a workspace, only the selected members are measured, whatever was
+    // built before, and no member's tests are
+    let root = test_dir.path.join("covered_ws");
+    write_file(
+        &root.join("Tailor.toml"),
+        "[workspace]\nmembers = [\"ws_a\", \"ws_b\"]\n",
+    );
+    for name in ["ws_a", "ws_b"] {
+        write_file(
+            &root.join(name).join("Tailor.toml"),
+            &format!(
+                "name = \"{}\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"lib\"\n",
+                name
+            ),
+        );
+        write_file(
+            &root.join(name).join(format!("src/{}.c", name)),
+            &format!("int {}_one(void) {{ return 1; }}\n", name),
+        );
+        write_file(
+            &root.join(name).join(format!("tests/{}_test.c", name)),
+            &format!(
+                "int {}_one(void);\n\nint main(void) {{ return {}_one() != 1; }}\n",
+                name, name
+            ),
+        );
+    }
+    let test_member = |name: &str| {
+        tailor([
+            "test".as_ref(),
+            root.as_os_str(),
+            "--coverage".as_ref(),
+            "-p".as_ref(),
+            name.as_ref(),
+        ])
+    };
+    assert_success(&test_member("ws_b"));
+    let output = test_member("ws_a");
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ws_a/src/ws_a.c"), "Got stdout: {}", stdout);
+    assert!(!stdout.contains("ws_b.c"), "Got stdout: {}", stdout);
+    assert!(!stdout.contains("tests/ws_"), "Got stdout: {}", stdout);
+}
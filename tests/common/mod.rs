@@ -0,0 +1,83 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Helper function to get the path to the tailor binary built by Cargo for tests.
+pub fn get_tailor_binary() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_tailor"))
+}
+
+pub struct TestDir {
+    pub path: PathBuf,
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        if self.path.exists() {
+            fs::remove_dir_all(&self.path).ok();
+        }
+    }
+}
+
+/// Setup: Creates and returns test directory path.
+/// Teardown is automatic via `Drop`, even if the test panics.
+pub fn setup_test_dir(name: &str) -> TestDir {
+    let test_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(name);
+    if test_dir.exists() {
+        fs::remove_dir_all(&test_dir).ok();
+    }
+    TestDir { path: test_dir }
+}
+
+/// Runs `tailor` with the given arguments and returns its output.
+pub fn tailor<I, S>(args: I) -> Output
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    Command::new(get_tailor_binary())
+        .args(args)
+        .output()
+        .expect("Failed to execute tailor")
+}
+
+/// Asserts that a `tailor` invocation succeeded, printing its stderr otherwise.
+pub fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "Command failed with stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Writes `content` to `path`, creating parent directories as needed.
+pub fn write_file(path: &Path, content: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("Failed to create parent directory");
+    }
+    fs::write(path, content).expect("Failed to write file");
+}
+
+/// Recursively collects every file under `root` with the given extension.
+pub fn find_files(root: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files = vec![];
+    let Ok(entries) = fs::read_dir(root) else {
+        return files;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(find_files(&path, extension));
+        } else if path.extension().is_some_and(|ext| ext == extension) {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    files
+}
@@ -312,3 +312,61 @@ fn test_new_library_package_with_existing_folder() {
         stderr
     );
 }
+
+#[test]
+fn test_new_header_only_package() {
+    let test_dir = setup_test_dir("myheaders");
+    let test_path = &test_dir.path;
+
+    // Run: tailor new --header-only myheaders
+    let output = Command::new(get_tailor_binary())
+        .arg("new")
+        .arg("--header-only")
+        .arg(test_path)
+        .output()
+        .expect("Failed to execute tailor");
+
+    // Verify command succeeded
+    assert!(
+        output.status.success(),
+        "Command failed with stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Verify output message
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Creating") && stdout.contains("header-only"),
+        "Output should contain 'Creating' and 'header-only' messages"
+    );
+
+    // Verify directory structure: headers only
+    assert!(
+        test_path.join("include/myheaders/myheaders.h").exists(),
+        "include/myheaders/myheaders.h was not created"
+    );
+    assert!(
+        !test_path.join("src").exists(),
+        "src directory should not be created"
+    );
+
+    // Verify header file content matches template exactly
+    let header_content = fs::read_to_string(test_path.join("include/myheaders/myheaders.h"))
+        .expect("Failed to read include/myheaders/myheaders.h");
+
+    let expected_header = "#ifndef MYHEADERS_H\n#define MYHEADERS_H\n\n#include <stdio.h>\n\nstatic inline void myheaders() { printf(\"Hello from the myheaders library!\\n\"); }\n\n#endif /* MYHEADERS_H */\n";
+    assert_eq!(
+        header_content, expected_header,
+        "myheaders.h content should match template exactly"
+    );
+
+    // Verify Tailor.toml content matches template exactly
+    let manifest_content =
+        fs::read_to_string(test_path.join("Tailor.toml")).expect("Failed to read Tailor.toml");
+
+    let expected_manifest = "name = \"myheaders\"\nversion = \"0.1.0\"\nedition = \"2026.1\"\ntype = \"header-only\"\n\n[dependencies]\n";
+    assert_eq!(
+        manifest_content, expected_manifest,
+        "Tailor.toml content should match template exactly"
+    );
+}